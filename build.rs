@@ -0,0 +1,45 @@
+//! Checks that the embedded wasm-pack executables are the version the package
+//! version says they are. See the version policy in the README.
+
+use std::path::Path;
+
+fn main() {
+    let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    println!("cargo:rerun-if-changed={}", src_dir.display());
+
+    let crate_version = env!("CARGO_PKG_VERSION");
+    let expected_version = crate_version
+        .split('-')
+        .next()
+        .expect("Split always returns at least one item.");
+
+    let entries = std::fs::read_dir(&src_dir).expect("Couldn't read the src directory.");
+    let mut found_any = false;
+    for entry in entries {
+        let entry = entry.expect("Couldn't read the src directory.");
+        let file_name = entry.file_name();
+        let Some(rest) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix("wasm-pack-v"))
+        else {
+            continue;
+        };
+        found_any = true;
+
+        let embedded_version = rest.split('-').next().unwrap_or_default();
+        if embedded_version != expected_version {
+            panic!(
+                "src/{} embeds wasm-pack {}, but the package version {} says it should be {}. \
+                Update the executables or the package version.",
+                file_name.to_string_lossy(),
+                embedded_version,
+                crate_version,
+                expected_version
+            );
+        }
+    }
+
+    if !found_any {
+        panic!("There are no embedded wasm-pack executables in src/.");
+    }
+}
@@ -0,0 +1,63 @@
+use std::ffi::OsStr;
+
+use crate::Executable;
+
+/// What wasm-pack is called in its own messages, rather than the path it was
+/// extracted to.
+const WASM_PACK_ARG0: &str = "wasm-pack";
+
+/// The `argv[0]` to give `executable`. Executables supplied by the caller
+/// keep their path, since they might be wrappers that care what they're
+/// called.
+pub(crate) fn for_executable(executable: &Executable) -> Option<&'static OsStr> {
+    match executable {
+        Executable::Embedded | Executable::Extracted(_) => Some(OsStr::new(WASM_PACK_ARG0)),
+        Executable::Custom(_) => None,
+    }
+}
+
+/// Start the process `expression` runs with `arg0` as its `argv[0]`. Only
+/// Unix supports this; elsewhere the executable's path is used.
+pub(crate) fn apply(expression: duct::Expression, arg0: &OsStr) -> duct::Expression {
+    let arg0 = arg0.to_os_string();
+    expression.before_spawn(move |command| {
+        apply_to_command(command, &arg0);
+        Ok(())
+    })
+}
+
+/// Like [`apply`], for a [`std::process::Command`].
+#[cfg(unix)]
+pub(crate) fn apply_to_command(command: &mut std::process::Command, arg0: &OsStr) {
+    use std::os::unix::process::CommandExt;
+
+    command.arg0(arg0);
+}
+
+#[cfg(not(unix))]
+pub(crate) fn apply_to_command(_command: &mut std::process::Command, _arg0: &OsStr) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn setting_argv0() {
+        let embedded = for_executable(&Executable::Embedded).unwrap();
+        assert_eq!(embedded, "wasm-pack");
+        assert_eq!(
+            for_executable(&Executable::Custom(PathBuf::from("sh"))),
+            None
+        );
+
+        // With `-c`, the shell's `$0` is its own `argv[0]`.
+        let expression = duct::cmd!("sh", "-c", "echo $0");
+        assert_eq!(apply(expression, embedded).read().unwrap(), "wasm-pack");
+
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "echo $0"]);
+        apply_to_command(&mut command, embedded);
+        assert_eq!(command.output().unwrap().stdout, b"wasm-pack\n");
+    }
+}
@@ -0,0 +1,38 @@
+/// A browser for `wasm-pack test` to run the tests in, set with
+/// [`WasmPack::browser`](crate::WasmPack::browser).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Browser {
+    /// Chrome, driven by chromedriver.
+    Chrome,
+    /// Firefox, driven by geckodriver.
+    Firefox,
+    /// Safari, driven by safaridriver. Only available on macOS.
+    Safari,
+}
+
+impl Browser {
+    /// The `wasm-pack test` flag that selects this browser.
+    pub(crate) fn flag(&self) -> &'static str {
+        match self {
+            Browser::Chrome => "--chrome",
+            Browser::Firefox => "--firefox",
+            Browser::Safari => "--safari",
+        }
+    }
+}
+
+/// The flags of every browser, to spot them in raw arguments.
+pub(crate) const BROWSER_FLAGS: [&str; 3] = ["--chrome", "--firefox", "--safari"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browser_flags() {
+        assert_eq!(Browser::Chrome.flag(), "--chrome");
+        assert_eq!(Browser::Firefox.flag(), "--firefox");
+        assert_eq!(Browser::Safari.flag(), "--safari");
+        assert!(BROWSER_FLAGS.contains(&Browser::Safari.flag()));
+    }
+}
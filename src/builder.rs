@@ -0,0 +1,2932 @@
+use std::ffi::{OsStr, OsString};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::browser::BROWSER_FLAGS;
+use crate::lines::LineCallback;
+use crate::snapshot::OutDirSnapshot;
+use crate::subcommand::private::Kind;
+use crate::subcommand::{
+    self, Build, CompilingSubcommand, CrateSubcommand, Login, Pack, Subcommand, Test,
+};
+
+use crate::{
+    Browser, BuildProfile, CommandRunner, Executable, OptLevel, OutputLine, OutputSink,
+    OutputStream, RecentLines, RunOptions, Target, WasmPackError, WasmPackOutput,
+};
+
+/// wasm-pack's default `--out-dir`.
+pub(crate) const DEFAULT_OUT_DIR: &str = "pkg";
+
+/// A typed builder for wasm-pack invocations.
+///
+/// The subcommand is part of the type, so only the methods that make sense
+/// for it are available, and running returns its own output type, like
+/// [`BuildOutput`](crate::BuildOutput) for builds.
+///
+/// ```no_run
+/// lib_wasm_pack::WasmPack::build("test-crate")
+///     // Like the raw `--out-dir` flag, this is relative to the input crate.
+///     .out_dir("../target/built-test-crate")
+///     .run()
+///     .expect("Running wasm-pack failed.");
+/// ```
+#[derive(Debug, Clone)]
+pub struct WasmPack<S = Build> {
+    subcommand: PhantomData<S>,
+    input_crate: PathBuf,
+    workspace_member: Option<String>,
+    manifest_path: Option<PathBuf>,
+    out_dir: Option<OutDir>,
+    lint_out_dir: bool,
+    no_install: bool,
+    deny_warnings: bool,
+    jobs: Option<usize>,
+    wasm_rustflags: Option<String>,
+    path_prefix: Vec<PathBuf>,
+    tool_cache_dir: Option<PathBuf>,
+    cargo_config_dir: Option<PathBuf>,
+    profile: Option<BuildProfile>,
+    cargo_profile: Option<String>,
+    debug_info: Option<bool>,
+    no_modules_global: Option<String>,
+    no_opt: bool,
+    browsers: Vec<Browser>,
+    node: bool,
+    headless: bool,
+    target: Option<Target>,
+    validate_output: bool,
+    wasm_opt_args: Vec<String>,
+    wasm_pack_args: Vec<OsString>,
+    cargo_args: Vec<OsString>,
+    features: Vec<String>,
+    all_features: bool,
+    no_default_features: bool,
+    gzip_level: Option<u32>,
+    wasm_size_budget: Option<u64>,
+    gzipped_wasm_size_budget: Option<u64>,
+    max_imports: Option<usize>,
+    message_format_json: bool,
+    write_build_info: bool,
+    ephemeral: bool,
+    wasm_pack_log_level: Option<log::Level>,
+    wasm_pack_quiet: bool,
+    arg_transforms: Vec<ArgsTransform>,
+    options: RunOptions,
+}
+
+#[derive(Clone)]
+struct ArgsTransform(Arc<dyn Fn(Vec<OsString>) -> Vec<OsString> + Send + Sync>);
+
+impl std::fmt::Debug for ArgsTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ArgsTransform")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OutDir {
+    RelativeToInputCrate(PathBuf),
+    RelativeToCurrentDir(PathBuf),
+    FromBase { base: PathBuf, out_dir: PathBuf },
+}
+
+impl WasmPack<Build> {
+    /// Start configuring `wasm-pack build` for the crate at `input_crate`,
+    /// relative to the current directory.
+    pub fn build(input_crate: impl Into<PathBuf>) -> Self {
+        Self::new(input_crate.into())
+    }
+}
+
+impl WasmPack<Test> {
+    /// Start configuring `wasm-pack test` for the crate at `input_crate`,
+    /// relative to the current directory.
+    ///
+    /// wasm-pack needs to be told where to run the tests, with
+    /// [`node`](Self::node) or [`browser`](Self::browser), or running fails
+    /// with [`WasmPackError::InvalidConfiguration`].
+    pub fn test(input_crate: impl Into<PathBuf>) -> Self {
+        Self::new(input_crate.into())
+    }
+
+    /// Run the tests in Node.js, with `--node`.
+    pub fn node(mut self, enabled: bool) -> Self {
+        self.node = enabled;
+        self
+    }
+
+    /// Run the tests in `browser` as well as any browsers already added.
+    /// wasm-pack runs them in each browser in turn, and needs the browser's
+    /// WebDriver, like chromedriver for [`Browser::Chrome`].
+    pub fn browser(mut self, browser: Browser) -> Self {
+        if !self.browsers.contains(&browser) {
+            self.browsers.push(browser);
+        }
+        self
+    }
+
+    /// Run the browsers without a window, with `--headless`. Setting this
+    /// without a [`browser`](Self::browser) is an
+    /// [`WasmPackError::InvalidConfiguration`] error, since wasm-pack refuses
+    /// it too.
+    pub fn headless(mut self, enabled: bool) -> Self {
+        self.headless = enabled;
+        self
+    }
+}
+
+impl WasmPack<Pack> {
+    /// Start configuring `wasm-pack pack` for the crate at `input_crate`,
+    /// relative to the current directory. The crate needs to have been built
+    /// into `pkg` already.
+    pub fn pack(input_crate: impl Into<PathBuf>) -> Self {
+        Self::new(input_crate.into())
+    }
+}
+
+impl WasmPack<subcommand::Version> {
+    /// Start configuring `wasm-pack --version`. Useful with
+    /// [`executable`](Self::executable), to check a custom executable.
+    pub fn version() -> Self {
+        Self::new(PathBuf::new())
+    }
+}
+
+impl WasmPack<Login> {
+    /// Start configuring `wasm-pack login`. It asks for credentials, so give
+    /// them to it with [`stdin`](Self::stdin).
+    pub fn login() -> Self {
+        Self::new(PathBuf::new())
+    }
+}
+
+impl<S: CrateSubcommand> WasmPack<S> {
+    /// Build the workspace member called `name` instead of the input crate
+    /// itself. The input crate path is then used to find the workspace, so it
+    /// can point at the workspace root or any directory inside it.
+    ///
+    /// The member is resolved with `cargo metadata` when running.
+    pub fn workspace_member(mut self, name: impl Into<String>) -> Self {
+        self.workspace_member = Some(name.into());
+        self
+    }
+
+    /// Use the crate whose manifest is at `manifest_path` as the input crate,
+    /// instead of the path passed to [`build`](Self::build).
+    ///
+    /// The path must point at a file called `Cargo.toml`. This is checked when
+    /// running.
+    pub fn manifest_path(mut self, manifest_path: impl Into<PathBuf>) -> Self {
+        self.manifest_path = Some(manifest_path.into());
+        self
+    }
+}
+
+impl WasmPack<Build> {
+    /// Set `--out-dir`.
+    ///
+    /// wasm-pack resolves this relative to the input crate, not the current
+    /// directory, so `target/pkg` ends up in `<input crate>/target/pkg`. Use
+    /// [`out_dir_relative_to`](Self::out_dir_relative_to) to avoid working
+    /// out the `../` by hand.
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(OutDir::RelativeToInputCrate(out_dir.into()));
+        self
+    }
+
+    /// Set `--out-dir` relative to the current directory, like the input
+    /// crate path. It's rewritten to be relative to the input crate before
+    /// being passed to wasm-pack, so `target/pkg` ends up in `./target/pkg`.
+    pub fn out_dir_from_current_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(OutDir::RelativeToCurrentDir(out_dir.into()));
+        self
+    }
+
+    /// Set `--out-dir` relative to `base`, which is relative to the current
+    /// directory unless it's absolute, like a workspace root. The path
+    /// wasm-pack expects is worked out when running, adding `../` to get out
+    /// of the input crate, so `.out_dir_relative_to("target", "pkg")` ends up
+    /// in `./target/pkg` however deep the input crate is.
+    ///
+    /// Absolute bases and out-dirs are made relative to the input crate too,
+    /// unless they're on another drive on Windows.
+    pub fn out_dir_relative_to(
+        mut self,
+        base: impl Into<PathBuf>,
+        out_dir: impl Into<PathBuf>,
+    ) -> Self {
+        self.out_dir = Some(OutDir::FromBase {
+            base: base.into(),
+            out_dir: out_dir.into(),
+        });
+        self
+    }
+
+    /// Whether to warn when the out-dir looks like it will end up inside the
+    /// input crate. Enabled by default.
+    pub fn lint_out_dir(mut self, enabled: bool) -> Self {
+        self.lint_out_dir = enabled;
+        self
+    }
+
+    /// Build with the given profile. wasm-pack defaults to
+    /// [`BuildProfile::Release`].
+    pub fn profile(mut self, profile: BuildProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Pass `flags` to rustc when compiling for wasm, like
+    /// `-C target-feature=+simd128`, without affecting build scripts and
+    /// proc macros compiled for the host.
+    ///
+    /// This sets `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUSTFLAGS` for
+    /// wasm-pack, which cargo only applies to the `wasm32-unknown-unknown`
+    /// target. Cargo ignores it if `RUSTFLAGS` or `CARGO_ENCODED_RUSTFLAGS`
+    /// is set, since those take precedence.
+    pub fn wasm_rustflags(mut self, flags: impl Into<String>) -> Self {
+        self.wasm_rustflags = Some(flags.into());
+        self
+    }
+
+    /// Build with a custom cargo profile from the crate's `Cargo.toml`.
+    ///
+    /// wasm-pack 0.12.1 always looks for the `.wasm` in cargo's `debug` or
+    /// `release` directory, so it can't package a build from any other
+    /// profile. Running returns a [`WasmPackError::UnsupportedOption`] error
+    /// explaining how to use the built-in profiles instead.
+    pub fn cargo_profile(mut self, name: impl Into<String>) -> Self {
+        self.cargo_profile = Some(name.into());
+        self
+    }
+
+    /// Keep or strip DWARF debug info in the generated `.wasm`, whatever the
+    /// profile.
+    ///
+    /// wasm-pack 0.12.1 has no flag for this, so running fails with
+    /// [`WasmPackError::UnsupportedOption`]. Set `dwarf-debug-info` in the
+    /// crate's `Cargo.toml` instead, and check the result with
+    /// [`BuildOutput::has_debug_info`](crate::BuildOutput::has_debug_info).
+    pub fn debug_info(mut self, enabled: bool) -> Self {
+        self.debug_info = Some(enabled);
+        self
+    }
+
+    /// Generate bindings for the given JS environment. wasm-pack defaults to
+    /// [`Target::Bundler`].
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Name the global that a [`Target::NoModules`] script defines, which is
+    /// `wasm_bindgen` by default. Setting this with any other target is an
+    /// [`WasmPackError::InvalidConfiguration`] error.
+    ///
+    /// wasm-pack 0.12.1 can't pass a different name on to wasm-bindgen, so
+    /// any name but `wasm_bindgen` fails with
+    /// [`WasmPackError::UnsupportedOption`]. Alias the global after loading the
+    /// script instead, like with `window.my_crate = wasm_bindgen;`.
+    pub fn no_modules_global(mut self, name: impl Into<String>) -> Self {
+        self.no_modules_global = Some(name.into());
+        self
+    }
+
+    /// After a successful build, check that the generated entry JS looks like
+    /// what wasm-pack generates for the target, and return a
+    /// [`WasmPackError::UnexpectedOutput`] error if it doesn't.
+    pub fn validate_output(mut self, enabled: bool) -> Self {
+        self.validate_output = enabled;
+        self
+    }
+
+    /// Arguments for wasm-opt, which wasm-pack runs on release builds.
+    ///
+    /// wasm-pack 0.12.1 can only be given these in the crate's Cargo.toml, so
+    /// running returns a [`WasmPackError::UnsupportedOption`] error explaining
+    /// how to set them there.
+    pub fn wasm_opt_args<I, A>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        self.wasm_opt_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Shorthand for passing an [`OptLevel`] to
+    /// [`wasm_opt_args`](Self::wasm_opt_args), with the same limitation.
+    pub fn optimization_level(self, level: OptLevel) -> Self {
+        self.wasm_opt_args([level.as_arg()])
+    }
+
+    /// Skip wasm-opt, for faster builds while iterating.
+    ///
+    /// wasm-pack 0.12.1 has no flag for this, so running fails with
+    /// [`WasmPackError::UnsupportedOption`]. Set `wasm-opt = false` in the
+    /// crate's `Cargo.toml` instead, or build with [`BuildProfile::Dev`], and
+    /// check with [`BuildOutput::wasm_opt_ran`](crate::BuildOutput::wasm_opt_ran).
+    /// Combining this with [`wasm_opt_args`](Self::wasm_opt_args) or
+    /// [`optimization_level`](Self::optimization_level) is an error.
+    pub fn no_opt(mut self, enabled: bool) -> Self {
+        self.no_opt = enabled;
+        self
+    }
+
+    /// After a successful build, compress the generated `*_bg.wasm` files to
+    /// `*_bg.wasm.gz` next to them. The sizes are reported by
+    /// [`WasmPackOutput::gzipped_wasm`].
+    ///
+    /// Uses the default compression level unless
+    /// [`gzip_level`](Self::gzip_level) is set.
+    pub fn gzip_output(mut self, enabled: bool) -> Self {
+        self.gzip_level = enabled.then(|| self.gzip_level.unwrap_or(DEFAULT_GZIP_LEVEL));
+        self
+    }
+
+    /// Compress the generated `*_bg.wasm` files with the given level, from 0
+    /// (no compression) to 9 (best compression). Implies
+    /// [`gzip_output(true)`](Self::gzip_output). Higher levels are a
+    /// [`WasmPackError::InvalidConfiguration`] error.
+    pub fn gzip_level(mut self, level: u32) -> Self {
+        self.gzip_level = Some(level);
+        self
+    }
+
+    /// After a successful build, return a [`WasmPackError::BudgetExceeded`]
+    /// error if the generated `*_bg.wasm` files add up to more than `bytes`.
+    /// The size is reported by [`BuildOutput::wasm_size`](crate::BuildOutput::wasm_size)
+    /// either way.
+    pub fn wasm_size_budget(mut self, bytes: u64) -> Self {
+        self.wasm_size_budget = Some(bytes);
+        self
+    }
+
+    /// Like [`wasm_size_budget`](Self::wasm_size_budget), but for the size of
+    /// the `*_bg.wasm` files once they're gzipped. Implies
+    /// [`gzip_output(true)`](Self::gzip_output), so turning gzipping back off
+    /// afterwards is a [`WasmPackError::InvalidConfiguration`] error.
+    pub fn gzipped_wasm_size_budget(mut self, bytes: u64) -> Self {
+        self.gzipped_wasm_size_budget = Some(bytes);
+        self.gzip_output(true)
+    }
+
+    /// Fail with [`WasmPackError::TooManyImports`] if the generated `.wasm`
+    /// has more than `max` imports, like for embedded hosts that only provide
+    /// a few. The error has all of the counts from
+    /// [`BuildOutput::wasm_stats`](crate::BuildOutput::wasm_stats). If the
+    /// `.wasm` can't be parsed, running fails with
+    /// [`WasmPackError::CouldntParseWasm`].
+    pub fn max_imports(mut self, max: usize) -> Self {
+        self.max_imports = Some(max);
+        self
+    }
+
+    /// Pass `--message-format=json` to cargo, so its errors and warnings can
+    /// be read from [`BuildOutput::cargo_messages`](crate::BuildOutput::cargo_messages),
+    /// or [`WasmPackError::cargo_messages`] if the build fails, with the
+    /// code they point at.
+    ///
+    /// Cargo then prints its messages to stdout as JSON rather than to stderr
+    /// as text, so [`warnings`](crate::BuildOutput::warnings) and
+    /// [`deny_warnings`](Self::deny_warnings) only see wasm-pack's own.
+    pub fn message_format_json(mut self, enabled: bool) -> Self {
+        self.message_format_json = enabled;
+        self
+    }
+
+    /// After a successful build, write a `build-info.json` file into the
+    /// out-dir, recording the wasm-pack version, when and on which platform it
+    /// ran, and its arguments. Arguments that look like secrets, like tokens
+    /// and credentials in URLs, are redacted.
+    pub fn write_build_info(mut self, enabled: bool) -> Self {
+        self.write_build_info = enabled;
+        self
+    }
+
+    /// Delete the out-dir when the returned [`BuildOutput`](crate::BuildOutput)
+    /// is dropped, or straight away if the build fails, like for builds in
+    /// tests that are thrown away afterwards. Call
+    /// [`persist`](crate::BuildOutput::persist) to keep it after all.
+    ///
+    /// An out-dir that already existed before the build is never deleted,
+    /// since it may hold files that aren't from this build.
+    pub fn ephemeral(mut self, enabled: bool) -> Self {
+        self.ephemeral = enabled;
+        self
+    }
+}
+
+impl<S: CompilingSubcommand> WasmPack<S> {
+    /// Pass `--mode no-install`, so wasm-pack uses the `wasm-bindgen` on the
+    /// `PATH` instead of downloading one. Useful for offline builds.
+    ///
+    /// Running fails early if `wasm-bindgen` isn't on the `PATH`.
+    pub fn no_install(mut self, enabled: bool) -> Self {
+        self.no_install = enabled;
+        self
+    }
+
+    /// Keep the tools wasm-pack downloads, like wasm-bindgen and wasm-opt, in
+    /// `dir` instead of the user's cache directory, by setting
+    /// `WASM_PACK_CACHE` for wasm-pack. Pointing this at a persistent
+    /// directory saves downloading them again on ephemeral CI machines.
+    ///
+    /// `dir` is created if it doesn't exist. Running fails with
+    /// [`WasmPackError::CouldntUseToolCacheDir`] if it can't be written to.
+    pub fn tool_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.tool_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Use `dir` as cargo's home directory, by setting `CARGO_HOME` for
+    /// wasm-pack, so every cargo command it runs reads the `config.toml` in
+    /// `dir`. This is for configuration that has to reach wasm-pack's cargo,
+    /// like replacing crates.io with a vendored registry for offline builds.
+    ///
+    /// Cargo also keeps its registry cache and git checkouts in `dir`, rather
+    /// than sharing the user's. Config in `dir` still takes a back seat to
+    /// any `.cargo/config.toml` in the crate or its parents. Relative paths in
+    /// the config, like a vendored source's `directory`, are relative to the
+    /// parent of `dir`.
+    ///
+    /// Running fails with [`WasmPackError::InvalidConfiguration`] if `dir`
+    /// doesn't have a readable `config.toml`, or the older `config`.
+    pub fn cargo_config_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cargo_config_dir = Some(dir.into());
+        self
+    }
+
+    /// Turn a successful run into a [`WasmPackError::WarningsDenied`] error if
+    /// wasm-pack or cargo printed any warnings. See
+    /// [`WasmPackOutput::warnings`] for what counts.
+    ///
+    /// This only sees warnings from wasm-pack's own build, not from clippy or
+    /// other tools.
+    pub fn deny_warnings(mut self, enabled: bool) -> Self {
+        self.deny_warnings = enabled;
+        self
+    }
+
+    /// Limit how many jobs cargo runs in parallel, by setting
+    /// `CARGO_BUILD_JOBS` for wasm-pack. Must be at least 1.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Limit the memory wasm-pack, cargo and the compiler can use to `bytes`
+    /// of address space, so a runaway build fails instead of taking the
+    /// machine down with it. Running out fails with
+    /// [`WasmPackError::MemoryLimitExceeded`].
+    ///
+    /// Only supported on Linux, where it's an `RLIMIT_AS` limit inherited by
+    /// everything wasm-pack runs. Elsewhere it's ignored with a warning.
+    pub fn memory_limit(mut self, bytes: u64) -> Self {
+        self.options.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Call `callback` with roughly how far along cargo's compile is, from 0
+    /// to 1, as it goes, like for a progress bar.
+    ///
+    /// This is a heuristic: it comes from the `done/total` counts in cargo's
+    /// own progress bar, which is turned on for wasm-pack's cargo by setting
+    /// `CARGO_TERM_PROGRESS_WHEN`, unless that's already set. Totals can grow
+    /// as cargo finds more to build, and the progress starts again from 0 if
+    /// wasm-pack runs cargo more than once, so it can go backwards. Nothing is
+    /// reported when there's nothing to compile. Updates arrive with the next
+    /// line of output, usually cargo's next "Compiling" line.
+    ///
+    /// The progress bar is taken out of the captured output and the lines
+    /// passed to [`on_output_line`](Self::on_output_line), but
+    /// [`output_sink`](Self::output_sink)s see it as printed. Only the
+    /// default [`DuctRunner`](crate::DuctRunner) streams output, so other
+    /// runners get no progress.
+    pub fn on_progress(mut self, callback: impl Fn(f32) + Send + Sync + 'static) -> Self {
+        self.options.on_progress = Some(crate::progress::ProgressCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Enable cargo features of the crate. Calling this again adds to the
+    /// features rather than replacing them.
+    ///
+    /// Feature flags always go after the `--` separator, so they reach cargo.
+    /// wasm-pack doesn't know them, so passing them with
+    /// [`raw_wasm_pack_arg`](Self::raw_wasm_pack_arg) is an error.
+    pub fn features<I, F>(mut self, features: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        self.features.extend(features.into_iter().map(Into::into));
+        self
+    }
+
+    /// Pass `--all-features` to cargo. See [`features`](Self::features).
+    pub fn all_features(mut self, enabled: bool) -> Self {
+        self.all_features = enabled;
+        self
+    }
+
+    /// Pass `--no-default-features` to cargo. See [`features`](Self::features).
+    pub fn no_default_features(mut self, enabled: bool) -> Self {
+        self.no_default_features = enabled;
+        self
+    }
+
+    /// Pass an argument through to cargo. Cargo arguments go after the input
+    /// crate path and a `--` separator.
+    pub fn raw_cargo_arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.cargo_args.push(arg.into());
+        self
+    }
+
+    /// Pass several arguments through to cargo, like
+    /// [`raw_cargo_arg`](Self::raw_cargo_arg). All cargo arguments share a
+    /// single `--` separator, so a leading `--` in `args` is dropped.
+    pub fn passthrough<I, A>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<OsString>,
+    {
+        let mut args = args.into_iter().map(Into::into).peekable();
+        args.next_if(|arg| arg == "--");
+        self.cargo_args.extend(args);
+        self
+    }
+}
+
+impl<S: Subcommand> WasmPack<S> {
+    fn new(input_crate: PathBuf) -> Self {
+        Self {
+            subcommand: PhantomData,
+            input_crate,
+            workspace_member: None,
+            manifest_path: None,
+            out_dir: None,
+            lint_out_dir: true,
+            no_install: false,
+            deny_warnings: false,
+            jobs: None,
+            wasm_rustflags: None,
+            path_prefix: Vec::new(),
+            tool_cache_dir: None,
+            cargo_config_dir: None,
+            profile: None,
+            cargo_profile: None,
+            debug_info: None,
+            no_modules_global: None,
+            no_opt: false,
+            browsers: Vec::new(),
+            node: false,
+            headless: false,
+            target: None,
+            validate_output: false,
+            wasm_opt_args: Vec::new(),
+            wasm_pack_args: Vec::new(),
+            cargo_args: Vec::new(),
+            features: Vec::new(),
+            all_features: false,
+            no_default_features: false,
+            gzip_level: None,
+            wasm_size_budget: None,
+            gzipped_wasm_size_budget: None,
+            max_imports: None,
+            message_format_json: false,
+            write_build_info: false,
+            ephemeral: false,
+            wasm_pack_log_level: None,
+            wasm_pack_quiet: false,
+            arg_transforms: Vec::new(),
+            options: RunOptions::default(),
+        }
+    }
+
+    /// How much wasm-pack itself should log. This is separate from this
+    /// crate's logging.
+    ///
+    /// wasm-pack 0.12.1's `--log-level` only goes down to `info`, and its
+    /// `-v` flag has no effect, so the levels map like this:
+    ///
+    /// - `Error`, `Warn` and `Info` pass `--log-level error`, `warn` and
+    ///   `info`.
+    /// - `Debug` and `Trace` pass `--log-level info`, and set `RUST_LOG` to
+    ///   `wasm_pack=debug` or `wasm_pack=trace`, which turns on wasm-pack's
+    ///   internal logging. Tools wasm-pack runs, like cargo and wasm-bindgen,
+    ///   aren't affected.
+    pub fn wasm_pack_log_level(mut self, level: log::Level) -> Self {
+        self.wasm_pack_log_level = Some(level);
+        self
+    }
+
+    /// Pass `--quiet`, so wasm-pack doesn't print its progress messages, like
+    /// `[INFO]: Compiling to Wasm...`, for less noise from routine builds.
+    ///
+    /// Errors are still printed, along with everything cargo prints, so
+    /// failures can be diagnosed as usual. Things worked out from
+    /// wasm-pack's messages, like
+    /// [`BuildOutput::wasm_opt_ran`](crate::BuildOutput::wasm_opt_ran), are
+    /// unknown, though.
+    pub fn wasm_pack_quiet(mut self, enabled: bool) -> Self {
+        self.wasm_pack_quiet = enabled;
+        self
+    }
+
+    /// Add a directory to the start of wasm-pack's `PATH`, so it can find
+    /// tools like `cargo` that aren't on the inherited `PATH`. Directories
+    /// added earlier come first. The current process's `PATH` isn't changed.
+    pub fn prepend_path(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.path_prefix.push(dir.into());
+        self
+    }
+
+    /// Pass an argument to wasm-pack that the builder doesn't have a method
+    /// for. It goes after the typed flags and before the input crate path.
+    pub fn raw_wasm_pack_arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.wasm_pack_args.push(arg.into());
+        self
+    }
+
+    /// Pass several arguments to wasm-pack verbatim, like
+    /// [`raw_wasm_pack_arg`](Self::raw_wasm_pack_arg). This is the escape
+    /// hatch for flags the typed methods don't cover, like ones from a newer
+    /// wasm-pack run with [`executable`](Self::executable).
+    ///
+    /// The arguments go after every typed flag, in the order they were added,
+    /// and before the input crate path. They can't go after the path, because
+    /// wasm-pack passes anything there on to cargo.
+    pub fn raw_args<I, A>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<OsString>,
+    {
+        self.wasm_pack_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keep the extracted wasm-pack executable in `target` between runs
+    /// instead of extracting and deleting it every time.
+    ///
+    /// Before reusing a cached executable, it's checked against the embedded
+    /// one and re-extracted if it doesn't match. Delete it with
+    /// [`clear_cache`](crate::clear_cache).
+    pub fn cache_executable(mut self, enabled: bool) -> Self {
+        self.options.cache_executable = enabled;
+        self
+    }
+
+    /// Name the extracted executable `wasm-pack-<platform>-v<version>-<suffix>`
+    /// using `suffix`, instead of a random UUID, like to get the same paths in
+    /// snapshot tests. Runs that happen at the same time need different
+    /// suffixes, or they'll overwrite each other's executable. Has no effect
+    /// with [`cache_executable`](Self::cache_executable).
+    pub fn temp_name_suffix(mut self, suffix: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        self.options.temp_name_suffix = Some(crate::NameSuffix(Arc::new(suffix)));
+        self
+    }
+
+    /// Where running extracts the executable for `platform` with these
+    /// options, without creating anything. `None` if nothing is extracted,
+    /// because an [`executable`](Self::executable) was given.
+    ///
+    /// Calls the [`temp_name_suffix`](Self::temp_name_suffix) callback if
+    /// there is one. Otherwise the name ends in a random UUID that the run
+    /// won't reuse, as with [`planned_temp_path`](crate::planned_temp_path).
+    pub fn planned_temp_path(&self, platform: crate::Platform) -> Option<PathBuf> {
+        match self.options.executable {
+            Executable::Embedded => Some(crate::planned_extraction_path(platform, &self.options)),
+            Executable::Extracted(_) | Executable::Custom(_) => None,
+        }
+    }
+
+    /// Always re-extract the cached executable, even if it looks valid. Only
+    /// has an effect together with [`cache_executable`](Self::cache_executable).
+    /// Setting [`FORCE_EXTRACT_ENV_VAR`](crate::FORCE_EXTRACT_ENV_VAR) does
+    /// the same for every run, without changing code.
+    pub fn force_extract(mut self, enabled: bool) -> Self {
+        self.options.force_extract = enabled;
+        self
+    }
+
+    /// Fail with [`WasmPackError::CouldntSaveCliExecutableToTemporaryFile`] if
+    /// the extracted executable can't be made executable. By default that's
+    /// only logged as a warning, since some filesystems refuse to change
+    /// permissions but can run the executable anyway.
+    pub fn strict_permissions(mut self, enabled: bool) -> Self {
+        self.options.strict_permissions = enabled;
+        self
+    }
+
+    /// The permission bits to give the extracted executable on Unix, like
+    /// `0o700` so only the owner can read and run it. Defaults to `0o755`.
+    /// Has no effect on Windows, or with a custom
+    /// [`executable`](Self::executable).
+    ///
+    /// The mode has to let the owner execute the file, since that's who runs
+    /// it, or running fails with [`WasmPackError::InvalidConfiguration`].
+    pub fn executable_mode(mut self, mode: u32) -> Self {
+        self.options.executable_mode = Some(mode);
+        self
+    }
+
+    /// Run the wasm-pack executable at `path` instead of the embedded one.
+    /// It's left in place afterwards.
+    pub fn executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.executable = Executable::Custom(path.into());
+        self
+    }
+
+    /// Run the embedded executable that a [`WasmPackSession`](crate::WasmPackSession)
+    /// already extracted to `path`.
+    pub(crate) fn extracted_executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.executable = Executable::Extracted(path.into());
+        self
+    }
+
+    /// Keep at most `limit` bytes each of wasm-pack's stdout and stderr. Longer
+    /// output keeps its start and end, with a marker in place of the middle,
+    /// and [`WasmPackOutput::was_truncated`] is set. The default
+    /// [`DuctRunner`](crate::DuctRunner) never buffers more than this, which
+    /// protects against runaway builds.
+    pub fn max_output_bytes(mut self, limit: usize) -> Self {
+        self.options.max_output_bytes = Some(limit);
+        self
+    }
+
+    /// Call `callback` with each line of wasm-pack's stdout and stderr as soon
+    /// as it's printed, rather than only seeing the output once wasm-pack has
+    /// finished. The output is still captured as usual, so combine this with
+    /// [`max_output_bytes`](Self::max_output_bytes) to avoid storing all of it.
+    ///
+    /// Setting this again replaces the previous callback, including the one
+    /// set by [`stream_into`](Self::stream_into). Only the default
+    /// [`DuctRunner`](crate::DuctRunner) streams output.
+    pub fn on_output_line(
+        mut self,
+        callback: impl Fn(&OutputLine) + Send + Sync + 'static,
+    ) -> Self {
+        self.options.on_line = Some(LineCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Call `callback` with the path of the executable and the final
+    /// arguments just before wasm-pack is started, like to log or audit the
+    /// exact command. This happens after the executable is extracted, on
+    /// every run, including ones started with
+    /// [`spawn_detached`](Self::spawn_detached).
+    ///
+    /// Arguments that look like secrets are redacted, the same way as in the
+    /// file written by [`write_build_info`](WasmPack::write_build_info).
+    pub fn on_command(
+        mut self,
+        callback: impl Fn(&Path, &[OsString]) + Send + Sync + 'static,
+    ) -> Self {
+        self.options.on_command = Some(crate::CommandCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Log each line of wasm-pack's stdout and stderr at `level` as soon as
+    /// it's printed, to the `lib_wasm_pack::output` target. Records have
+    /// `run_id` and `stream` key-values, and the message starts with the run
+    /// ID like the crate's other logs.
+    ///
+    /// This works alongside [`on_output_line`](Self::on_output_line). To get
+    /// the lines as `tracing` events, forward `log` records to `tracing`, like
+    /// with `tracing_log::LogTracer`.
+    pub fn log_output(mut self, level: log::Level) -> Self {
+        self.options.log_output = Some(level);
+        self
+    }
+
+    /// Keep the last lines of wasm-pack's output in `recent_lines` as it runs.
+    /// A shorthand for [`on_output_line`](Self::on_output_line).
+    pub fn stream_into(self, recent_lines: RecentLines) -> Self {
+        self.on_output_line(move |line| recent_lines.push(line))
+    }
+
+    /// Send `stream` to `sink` as it's read, instead of capturing it in
+    /// memory. Use [`sink::Tee`](crate::sink::Tee) with
+    /// [`sink::InMemory`](crate::sink::InMemory) to do both, like to keep a
+    /// log file while still getting the output back:
+    ///
+    /// ```no_run
+    /// use lib_wasm_pack::{sink, OutputStream, WasmPack};
+    ///
+    /// let log = sink::File::create("target/wasm-pack-stderr.log").unwrap();
+    /// WasmPack::build("test-crate")
+    ///     .output_sink(OutputStream::Stderr, sink::Tee::new(sink::InMemory, log))
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// A stream that isn't captured is empty in the run's output, and in the
+    /// output held by errors. Setting this again for the same stream replaces
+    /// the previous sink. Only the default [`DuctRunner`](crate::DuctRunner)
+    /// drives sinks.
+    pub fn output_sink(mut self, stream: OutputStream, sink: impl OutputSink + 'static) -> Self {
+        let sink: Arc<dyn OutputSink> = Arc::new(sink);
+        match stream {
+            OutputStream::Stdout => self.options.stdout_sink = Some(sink),
+            OutputStream::Stderr => self.options.stderr_sink = Some(sink),
+        }
+        self
+    }
+
+    /// Show wasm-pack's output on this process's stdout and stderr as it's
+    /// printed, like when running it in a terminal, while still capturing it.
+    /// A shorthand for [`output_sink`](Self::output_sink) with a
+    /// [`sink::Tee`](crate::sink::Tee) of [`sink::InMemory`](crate::sink::InMemory)
+    /// and [`sink::Stdout`](crate::sink::Stdout) or
+    /// [`sink::Stderr`](crate::sink::Stderr) for each stream.
+    ///
+    /// wasm-pack writes to pipes, which are read and written back out, so its
+    /// output isn't colored, and lines from stdout and stderr printed at
+    /// almost the same time may swap places. Disabling this goes back to only
+    /// capturing.
+    pub fn inherit_and_capture(self, enabled: bool) -> Self {
+        use crate::sink::{InMemory, Stderr, Stdout, Tee};
+
+        if !enabled {
+            return self
+                .output_sink(OutputStream::Stdout, InMemory)
+                .output_sink(OutputStream::Stderr, InMemory);
+        }
+
+        self.output_sink(OutputStream::Stdout, Tee::new(InMemory, Stdout))
+            .output_sink(OutputStream::Stderr, Tee::new(InMemory, Stderr))
+    }
+
+    /// Feed `bytes` to wasm-pack's stdin, like the answers to
+    /// [`login`](WasmPack::login)'s prompts.
+    ///
+    /// Otherwise wasm-pack's stdin is empty, so anything that asks for input
+    /// fails straight away, rather than waiting for an answer to a prompt
+    /// that can't be seen because the output is captured. Custom
+    /// [`CommandRunner`]s should do the same.
+    pub fn stdin(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.options.stdin = Some(bytes.into());
+        self
+    }
+
+    /// How many of the last lines of wasm-pack's stdout and stderr to show
+    /// when displaying a [`WasmPackError::WasmPackReturnedAnError`] error.
+    /// Defaults to [`DEFAULT_ERROR_TAIL_LINES`](crate::DEFAULT_ERROR_TAIL_LINES).
+    /// The error still holds all of the output, which
+    /// [`WasmPackError::full_stdout`] and [`WasmPackError::full_stderr`]
+    /// return.
+    pub fn error_tail_lines(mut self, lines: usize) -> Self {
+        self.options.error_tail_lines = Some(lines);
+        self
+    }
+
+    /// Whether to stop wasm-pack when this process gets a SIGINT, like from
+    /// Ctrl-C. Enabled by default. While wasm-pack runs, a SIGINT handler is
+    /// installed, and running returns a [`WasmPackError::Interrupted`] error
+    /// instead of the signal's usual effect, so you can exit as you see fit.
+    ///
+    /// Disable this if you manage signals yourself. Only has an effect on Unix,
+    /// with the default [`DuctRunner`](crate::DuctRunner).
+    pub fn forward_interrupts(mut self, enabled: bool) -> Self {
+        self.options.ignore_interrupts = !enabled;
+        self
+    }
+
+    /// Run the wasm-pack executable with `runner` instead of the default
+    /// [`DuctRunner`](crate::DuctRunner). See [`CommandRunner`] for an example.
+    pub fn command_runner(mut self, runner: impl CommandRunner + 'static) -> Self {
+        self.options.runner = Some(std::sync::Arc::new(runner));
+        self
+    }
+
+    /// Rewrite the final arguments just before wasm-pack runs, like to add
+    /// flags every invocation should have regardless of where it's
+    /// configured. If this is called more than once, the transforms run in
+    /// the order they were added.
+    ///
+    /// The transformed arguments are the ones returned by
+    /// [`args`](Self::args), logged, and recorded in build info.
+    pub fn transform_args(
+        mut self,
+        transform: impl Fn(Vec<OsString>) -> Vec<OsString> + Send + Sync + 'static,
+    ) -> Self {
+        self.arg_transforms.push(ArgsTransform(Arc::new(transform)));
+        self
+    }
+
+    /// The arguments that will be passed to wasm-pack.
+    ///
+    /// They're always in this order: the subcommand, wasm-pack flags, the
+    /// input crate path, then `--` followed by cargo arguments. `--version`
+    /// takes the place of the subcommand, and has no input crate. Any
+    /// [`transform_args`](Self::transform_args) transforms have already been
+    /// applied.
+    ///
+    /// This fails if a workspace member was requested and couldn't be resolved.
+    pub fn args(&self) -> Result<Vec<OsString>, WasmPackError> {
+        self.validate()?;
+        let input_crate = self.resolve_input_crate()?;
+        Ok(self.args_for(&input_crate))
+    }
+
+    fn args_for(&self, input_crate: &Path) -> Vec<OsString> {
+        let args = self.untransformed_args_for(input_crate);
+        self.arg_transforms
+            .iter()
+            .fold(args, |args, transform| (transform.0)(args))
+    }
+
+    fn untransformed_args_for(&self, input_crate: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+
+        // `--quiet` and `--log-level` aren't global flags, so they have to go
+        // before the subcommand. After it, wasm-pack passes them to cargo.
+        if self.wasm_pack_quiet {
+            args.push("--quiet".into());
+        }
+        if let Some(level) = self.wasm_pack_log_level {
+            args.push("--log-level".into());
+            args.push(log_level_arg(level).into());
+        }
+
+        match S::KIND.name() {
+            Some(name) => args.push(name.into()),
+            None => args.push("--version".into()),
+        }
+
+        if let Some(profile) = self.profile {
+            args.push(profile.flag().into());
+        }
+
+        if let Some(target) = self.target {
+            args.push("--target".into());
+            args.push(target.as_str().into());
+        }
+
+        if self.node {
+            args.push("--node".into());
+        }
+        args.extend(self.browsers.iter().map(|browser| browser.flag().into()));
+        if self.headless {
+            args.push("--headless".into());
+        }
+
+        if self.no_install {
+            args.push("--mode".into());
+            args.push("no-install".into());
+        }
+
+        if let Some(out_dir) = self.out_dir_arg(input_crate) {
+            // A long out-dir is passed as an extended-length path on Windows,
+            // which has to be absolute, so it's no longer relative to the
+            // input crate.
+            let out_dir =
+                crate::paths::extended_length_path(&input_crate.join(&out_dir)).unwrap_or(out_dir);
+            args.push("--out-dir".into());
+            args.push(out_dir.into());
+        }
+
+        args.extend(self.wasm_pack_args.iter().cloned());
+        if !S::KIND.takes_crate() {
+            return args;
+        }
+        args.push(input_crate.into());
+
+        let mut cargo_flags = self.feature_args();
+        if self.message_format_json {
+            cargo_flags.push("--message-format=json".into());
+        }
+        if !self.cargo_args.is_empty() || !cargo_flags.is_empty() {
+            args.push("--".into());
+            args.extend(cargo_flags);
+            args.extend(self.cargo_args.iter().cloned());
+        }
+
+        args
+    }
+
+    /// The cargo feature flags, which have to go after the `--` separator.
+    /// Before it, wasm-pack would take them as its own arguments.
+    fn feature_args(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        if !self.features.is_empty() {
+            args.push("--features".into());
+            args.push(self.features.join(",").into());
+        }
+        if self.all_features {
+            args.push("--all-features".into());
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".into());
+        }
+        args
+    }
+
+    fn validate(&self) -> Result<(), WasmPackError> {
+        if let Some(mode) = self.options.executable_mode {
+            if mode > 0o7777 || mode & 0o100 == 0 {
+                let reason = format!(
+                    "The executable mode {:o} doesn't let the owner execute the file",
+                    mode
+                );
+                return Err(WasmPackError::InvalidConfiguration(reason));
+            }
+        }
+
+        if let Some(arg) = self.wasm_pack_args.iter().find(|arg| is_feature_flag(arg)) {
+            let reason = format!(
+                "{:?} is a cargo flag, so it has to go after the `--` separator. Use `features`, `all_features` or `no_default_features` instead",
+                arg
+            );
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        let has_message_format = self
+            .cargo_args
+            .iter()
+            .any(|arg| arg.to_string_lossy().starts_with("--message-format"));
+        if self.message_format_json && has_message_format {
+            let reason =
+                "`message_format_json` can't be combined with a `--message-format` cargo argument"
+                    .to_string();
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        if self.jobs == Some(0) {
+            let reason = "`jobs` must be at least 1".to_string();
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        if let Some(level) = self.gzip_level {
+            crate::gzip::check_level(level)
+                .map_err(|error| WasmPackError::InvalidConfiguration(error.to_string()))?;
+        }
+
+        if self.gzipped_wasm_size_budget.is_some() && self.gzip_level.is_none() {
+            let reason =
+                "`gzipped_wasm_size_budget` can't be checked with `gzip_output(false)`".to_string();
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        if S::KIND == Kind::Test {
+            self.validate_test_environment()?;
+        }
+
+        if self.no_opt && !self.wasm_opt_args.is_empty() {
+            let reason = "Can't skip wasm-opt and pass it arguments at the same time".to_string();
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        if self.no_opt {
+            return Err(WasmPackError::UnsupportedOption {
+                option: "no_opt".to_string(),
+                reason: crate::wasm_opt::no_opt_unsupported_reason(),
+            });
+        }
+
+        if let Some(name) = &self.no_modules_global {
+            if self.target != Some(Target::NoModules) {
+                let reason = "`no_modules_global` only applies to `Target::NoModules`".to_string();
+                return Err(WasmPackError::InvalidConfiguration(reason));
+            }
+
+            if name != crate::target::NO_MODULES_GLOBAL {
+                return Err(WasmPackError::UnsupportedOption {
+                    option: "no_modules_global".to_string(),
+                    reason: format!(
+                        "wasm-pack {} always names the global `{}`. Alias it after loading the script instead, like with `window.{} = {};`",
+                        crate::WASM_PACK_VERSION,
+                        crate::target::NO_MODULES_GLOBAL,
+                        name,
+                        crate::target::NO_MODULES_GLOBAL
+                    ),
+                });
+            }
+        }
+
+        if let Some(cargo_profile) = &self.cargo_profile {
+            return Err(WasmPackError::UnsupportedOption {
+                option: "cargo_profile".to_string(),
+                reason: format!(
+                    "wasm-pack {} always packages the `.wasm` from cargo's `debug` or `release` directory, so it can't use the `{}` profile. Set its options on `[profile.dev]` or `[profile.release]` in the crate's Cargo.toml instead, and pick one with `profile`",
+                    crate::WASM_PACK_VERSION,
+                    cargo_profile
+                ),
+            });
+        }
+
+        if let Some(enabled) = self.debug_info {
+            return Err(WasmPackError::UnsupportedOption {
+                option: "debug_info".to_string(),
+                reason: crate::debug_info::unsupported_reason(enabled),
+            });
+        }
+
+        if !self.wasm_opt_args.is_empty() {
+            return Err(WasmPackError::UnsupportedOption {
+                option: "wasm_opt_args".to_string(),
+                reason: crate::wasm_opt::unsupported_reason(&self.wasm_opt_args),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check that `wasm-pack test` is told where to run the tests, counting
+    /// flags passed with [`raw_args`](Self::raw_args), the way wasm-pack
+    /// checks it.
+    fn validate_test_environment(&self) -> Result<(), WasmPackError> {
+        let has_raw_flag = |flags: &[&str]| {
+            self.wasm_pack_args
+                .iter()
+                .any(|arg| flags.iter().any(|flag| arg == *flag))
+        };
+
+        let has_browser = !self.browsers.is_empty() || has_raw_flag(&BROWSER_FLAGS);
+        if !has_browser && !self.node && !has_raw_flag(&["--node"]) {
+            let reason = "`wasm-pack test` needs `node` or at least one `browser`".to_string();
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        if self.headless && !has_browser {
+            let reason = "`headless` only applies to tests run in a browser".to_string();
+            return Err(WasmPackError::InvalidConfiguration(reason));
+        }
+
+        Ok(())
+    }
+
+    /// The `PATH` wasm-pack will see.
+    fn child_path(&self) -> Result<OsString, WasmPackError> {
+        let inherited_path = std::env::var_os("PATH").unwrap_or_default();
+        if self.path_prefix.is_empty() {
+            return Ok(inherited_path);
+        }
+
+        let dirs = self
+            .path_prefix
+            .iter()
+            .cloned()
+            .chain(std::env::split_paths(&inherited_path));
+        std::env::join_paths(dirs)
+            .map_err(|error| WasmPackError::InvalidConfiguration(error.to_string()))
+    }
+
+    /// The run options, plus any that depend on the builder's other settings.
+    fn run_options(&self) -> Result<RunOptions, WasmPackError> {
+        let mut options = self.options.clone();
+
+        if !self.path_prefix.is_empty() {
+            options.env.push(("PATH".into(), self.child_path()?));
+        }
+
+        if let Some(filter) = self.wasm_pack_log_level.and_then(rust_log_filter) {
+            options.env.push(("RUST_LOG".into(), filter.into()));
+        }
+
+        if let Some(dir) = &self.tool_cache_dir {
+            check_writable(dir).map_err(WasmPackError::CouldntUseToolCacheDir)?;
+            options
+                .env
+                .push(("WASM_PACK_CACHE".into(), dir.clone().into()));
+        }
+
+        if let Some(dir) = &self.cargo_config_dir {
+            // Cargo runs in the input crate, so a relative path would point
+            // somewhere else.
+            let dir = check_cargo_config_dir(dir)?;
+            options.env.push(("CARGO_HOME".into(), dir.into()));
+        }
+
+        if let Some(flags) = &self.wasm_rustflags {
+            options.env.push((
+                "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUSTFLAGS".into(),
+                flags.into(),
+            ));
+        }
+
+        if let Some(jobs) = self.jobs {
+            options
+                .env
+                .push(("CARGO_BUILD_JOBS".into(), jobs.to_string().into()));
+        }
+
+        // Respect a progress bar setting from the environment.
+        if options.on_progress.is_some() && std::env::var_os("CARGO_TERM_PROGRESS_WHEN").is_none() {
+            for (name, value) in crate::progress::PROGRESS_BAR_ENV {
+                options.env.push((name.into(), value.into()));
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn resolve_input_crate(&self) -> Result<PathBuf, WasmPackError> {
+        let input_crate = match &self.manifest_path {
+            Some(manifest_path) => crate_dir_from_manifest_path(manifest_path)?,
+            None => self.input_crate.clone(),
+        };
+
+        match &self.workspace_member {
+            Some(name) => crate::workspace::find_member_dir(&input_crate, name),
+            None => Ok(input_crate),
+        }
+    }
+
+    /// Run wasm-pack with the configured arguments.
+    pub fn run(&self) -> Result<S::Output, WasmPackError> {
+        let result = self.run_and_check();
+        if let Err(error) = &result {
+            crate::diagnostics::record_error(error);
+        }
+        result.map(S::wrap)
+    }
+
+    /// Extract wasm-pack and return a `duct` expression that runs it with the
+    /// configured arguments and environment, without running it. See
+    /// [`WasmPackExpression`](crate::WasmPackExpression).
+    pub fn to_expression(&self) -> Result<crate::WasmPackExpression, WasmPackError> {
+        self.validate()?;
+        let input_crate = self.resolve_input_crate()?;
+        crate::expression::expression_with_options(
+            self.args_for(&input_crate),
+            &self.run_options()?,
+        )
+    }
+
+    /// A POSIX `sh` script that runs wasm-pack the way [`run`](Self::run)
+    /// would, for reproducing a build by hand or documenting it. It exports
+    /// the environment variables the builder sets, then runs `wasm-pack` from
+    /// the `PATH` with the configured arguments, quoted for the shell.
+    ///
+    /// The script has to be run from the current directory, since paths in
+    /// the arguments are relative to it. A custom
+    /// [`executable`](Self::executable) is run by its path rather than as
+    /// `wasm-pack`, and [`stdin`](Self::stdin) isn't included.
+    pub fn to_shell_script(&self) -> Result<String, WasmPackError> {
+        self.validate()?;
+        let input_crate = self.resolve_input_crate()?;
+        let options = self.run_options()?;
+        let program = match &options.executable {
+            Executable::Custom(path) => crate::shell_script::quote(&path.to_string_lossy()),
+            Executable::Embedded | Executable::Extracted(_) => "wasm-pack".to_string(),
+        };
+
+        Ok(crate::shell_script::render(
+            &program,
+            &self.args_for(&input_crate),
+            &options.env,
+        ))
+    }
+
+    /// Start wasm-pack in the background and return without waiting for it,
+    /// for builds that should outlive whatever started them.
+    ///
+    /// wasm-pack runs in its own session on Unix, and detached from the
+    /// console on Windows, so it isn't stopped by signals meant for this
+    /// process. Its stdout and stderr are written to `wasm-pack-stdout.log`
+    /// and `wasm-pack-stderr.log` in `log_dir`. The embedded executable is
+    /// extracted as if [`cache_executable`](Self::cache_executable) were set.
+    ///
+    /// None of the checks that [`run`](Self::run) does after wasm-pack
+    /// finishes are done.
+    pub fn spawn_detached(
+        &self,
+        log_dir: impl AsRef<Path>,
+    ) -> Result<crate::DetachedBuild, WasmPackError> {
+        self.validate()?;
+        let input_crate = self.resolve_input_crate()?;
+        crate::detached::spawn_detached(
+            self.args_for(&input_crate),
+            &self.run_options()?,
+            log_dir.as_ref(),
+        )
+    }
+
+    fn run_and_check(&self) -> Result<WasmPackOutput, WasmPackError> {
+        self.validate()?;
+
+        if self.no_install && find_on_path("wasm-bindgen", &self.child_path()?).is_none() {
+            return Err(WasmPackError::WasmBindgenNotFound);
+        }
+
+        let input_crate = self.resolve_input_crate()?;
+        if self.lint_out_dir {
+            self.warn_about_out_dir(&input_crate);
+        }
+
+        let args = self.args_for(&input_crate);
+        let before_build = match S::KIND {
+            Kind::Build => OutDirSnapshot::take(&self.resolve_out_dir(&input_crate))
+                .map_err(WasmPackError::CouldntReadOutDir)?,
+            _ => OutDirSnapshot::default(),
+        };
+        // Guard the out-dir before running, so it's deleted if the build fails.
+        let out_dir_guard = match S::KIND {
+            Kind::Build if self.ephemeral => {
+                crate::out_dir_guard::OutDirGuard::new(self.resolve_out_dir(&input_crate))
+            }
+            _ => None,
+        };
+        let mut output = crate::run_with_options(args.clone(), &self.run_options()?)?;
+        output.set_out_dir_guard(out_dir_guard);
+        // A transform can rename or drop the subcommand, so the profile can't
+        // always be worked out from the final arguments.
+        if output.profile().is_none() {
+            output.set_profile(BuildProfile::from_args(
+                &self.untransformed_args_for(&input_crate),
+            ));
+        }
+
+        if S::KIND == Kind::Build {
+            let out_dir = self.resolve_out_dir(&input_crate);
+            output
+                .set_out_dir(out_dir, &before_build)
+                .map_err(WasmPackError::CouldntReadOutDir)?;
+            let output_name =
+                crate::output_name::resolve(&args, output.generated_files(), &input_crate);
+            output.set_output_name(output_name);
+        }
+
+        if self.validate_output {
+            self.check_output(&output)?;
+        }
+
+        if self.deny_warnings {
+            let warnings = output.warnings();
+            if !warnings.is_empty() {
+                let warnings = warnings.into_iter().map(String::from).collect();
+                return Err(WasmPackError::WarningsDenied { warnings });
+            }
+        }
+
+        if let Some(level) = self.gzip_level {
+            let gzipped = crate::gzip::gzip_wasm_files(output.generated_files(), level)
+                .map_err(WasmPackError::CouldntGzipOutput)?;
+            output.set_gzipped_wasm(gzipped);
+        }
+
+        if let Some(budget) = self.wasm_size_budget {
+            check_budget(output.wasm_size(), budget, false)?;
+        }
+
+        if let Some(budget) = self.gzipped_wasm_size_budget {
+            let actual = output
+                .gzipped_wasm()
+                .iter()
+                .map(|gzipped| gzipped.compressed_size())
+                .sum();
+            check_budget(actual, budget, true)?;
+        }
+
+        if let Some(max_imports) = self.max_imports {
+            let stats = crate::wasm_stats::for_files(output.generated_files()).map_err(
+                |error| match error.kind() {
+                    std::io::ErrorKind::InvalidData => WasmPackError::CouldntParseWasm(error),
+                    _ => WasmPackError::CouldntReadOutDir(error),
+                },
+            )?;
+            if stats.imports > max_imports {
+                return Err(WasmPackError::TooManyImports { stats, max_imports });
+            }
+        }
+
+        if self.write_build_info {
+            let out_dir = self.resolve_out_dir(&input_crate);
+            crate::build_info::write_build_info(&out_dir, &output, &args)
+                .map_err(WasmPackError::CouldntWriteBuildInfo)?;
+        }
+
+        Ok(output)
+    }
+
+    fn check_output(&self, output: &WasmPackOutput) -> Result<(), WasmPackError> {
+        let unexpected = |reason: String| WasmPackError::UnexpectedOutput { reason };
+
+        let entry_js_path = crate::target::find_entry_js(output.generated_files())
+            .ok_or_else(|| unexpected("there's no generated JS file".to_string()))?;
+        let entry_js = std::fs::read_to_string(entry_js_path)
+            .map_err(|error| unexpected(format!("couldn't read {:?}: {}", entry_js_path, error)))?;
+
+        self.target
+            .unwrap_or(Target::Bundler)
+            .check_entry_js(&entry_js)
+            .map_err(unexpected)
+    }
+
+    /// The `--out-dir` to pass to wasm-pack, relative to the input crate.
+    fn out_dir_arg(&self, input_crate: &Path) -> Option<PathBuf> {
+        match self.out_dir.as_ref()? {
+            OutDir::RelativeToInputCrate(out_dir) => Some(out_dir.clone()),
+            OutDir::RelativeToCurrentDir(out_dir) => Some(
+                crate::paths::out_dir_relative_to_input_crate(input_crate, out_dir),
+            ),
+            OutDir::FromBase { base, out_dir } => Some(crate::paths::out_dir_relative_to(
+                input_crate,
+                base,
+                out_dir,
+            )),
+        }
+    }
+
+    /// Where wasm-pack will put the output, relative to the current directory.
+    fn resolve_out_dir(&self, input_crate: &Path) -> PathBuf {
+        let out_dir = self
+            .out_dir_arg(input_crate)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_OUT_DIR));
+        input_crate.join(out_dir)
+    }
+
+    fn warn_about_out_dir(&self, input_crate: &Path) {
+        if let Some(warning) = self.out_dir_warning(input_crate) {
+            log::warn!("{}", warning);
+        }
+    }
+
+    fn out_dir_warning(&self, input_crate: &Path) -> Option<String> {
+        let Some(OutDir::RelativeToInputCrate(out_dir)) = &self.out_dir else {
+            return None;
+        };
+
+        out_dir_is_probably_a_mistake(out_dir).then(|| {
+            format!(
+                "The out-dir {:?} is relative to the input crate, so the output will end up in {:?}. \
+                If that's not what you want, prefix it with `../`. \
+                Disable this warning with `.lint_out_dir(false)`.",
+                out_dir,
+                self.resolve_out_dir(input_crate)
+            )
+        })
+    }
+}
+
+fn crate_dir_from_manifest_path(manifest_path: &Path) -> Result<PathBuf, WasmPackError> {
+    if manifest_path.file_name() != Some("Cargo.toml".as_ref()) {
+        return Err(WasmPackError::ManifestPathIsntCargoToml {
+            path: manifest_path.to_path_buf(),
+        });
+    }
+
+    if !manifest_path.is_file() {
+        return Err(WasmPackError::ManifestNotFound {
+            path: manifest_path.to_path_buf(),
+        });
+    }
+
+    // The parent of a bare `Cargo.toml` is an empty path.
+    let crate_dir = manifest_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    Ok(crate_dir.to_path_buf())
+}
+
+/// Find an executable called `name` in the directories listed in `path`,
+/// which is formatted like the `PATH` environment variable.
+pub(crate) fn find_on_path(name: &str, path: &OsStr) -> Option<PathBuf> {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Create `dir` if it doesn't exist, and check that files can be created in
+/// it.
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".lib-wasm-pack-probe-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+/// Check that `dir` has a cargo config file that can be read, and return it
+/// as an absolute path.
+fn check_cargo_config_dir(dir: &Path) -> Result<PathBuf, WasmPackError> {
+    let invalid = |problem: String| {
+        let reason = format!("The cargo config dir {:?} {}", dir, problem);
+        WasmPackError::InvalidConfiguration(reason)
+    };
+
+    // Cargo prefers `config.toml`, but still reads `config` from before it
+    // had the extension.
+    let config_file = ["config.toml", "config"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| invalid("doesn't have a `config.toml`".to_string()))?;
+    std::fs::read_to_string(&config_file)
+        .map_err(|error| invalid(format!("has a config file that can't be read: {}", error)))?;
+
+    std::path::absolute(dir).map_err(|error| invalid(error.to_string()))
+}
+
+/// Whether `arg` is one of cargo's feature flags, like `--features=serde`.
+fn is_feature_flag(arg: &OsStr) -> bool {
+    let arg = arg.to_string_lossy();
+    let name = arg.split('=').next().unwrap_or_default();
+    [
+        "--features",
+        "-F",
+        "--all-features",
+        "--no-default-features",
+    ]
+    .contains(&name)
+}
+
+fn log_level_arg(level: log::Level) -> &'static str {
+    match level {
+        log::Level::Error => "error",
+        log::Level::Warn => "warn",
+        log::Level::Info | log::Level::Debug | log::Level::Trace => "info",
+    }
+}
+
+/// The `RUST_LOG` for levels `--log-level` can't express.
+fn rust_log_filter(level: log::Level) -> Option<&'static str> {
+    match level {
+        log::Level::Debug => Some("wasm_pack=debug"),
+        log::Level::Trace => Some("wasm_pack=trace"),
+        log::Level::Error | log::Level::Warn | log::Level::Info => None,
+    }
+}
+
+fn check_budget(actual: u64, budget: u64, gzipped: bool) -> Result<(), WasmPackError> {
+    if actual > budget {
+        return Err(WasmPackError::BudgetExceeded {
+            actual,
+            budget,
+            gzipped,
+        });
+    }
+    Ok(())
+}
+
+/// flate2's default compression level.
+const DEFAULT_GZIP_LEVEL: u32 = 6;
+
+/// wasm-pack resolves relative out-dirs from the input crate, so a relative
+/// out-dir that doesn't go up a directory is almost always a mistake.
+fn out_dir_is_probably_a_mistake(out_dir: &Path) -> bool {
+    // `.` is the crate itself, which nobody asks for by accident.
+    out_dir.is_relative() && !out_dir.starts_with("..") && out_dir != Path::new(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_args_are_in_the_right_order() {
+        let args = WasmPack::build("test-crate")
+            .out_dir("../target/built-test-crate")
+            .args()
+            .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "--out-dir",
+                "../target/built-test-crate",
+                "test-crate"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_environment_args() {
+        let args = WasmPack::test("test-crate")
+            .node(true)
+            .browser(Browser::Chrome)
+            .browser(Browser::Firefox)
+            .browser(Browser::Safari)
+            .browser(Browser::Chrome)
+            .headless(true)
+            .args()
+            .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "test",
+                "--node",
+                "--chrome",
+                "--firefox",
+                "--safari",
+                "--headless",
+                "test-crate"
+            ]
+        );
+
+        let args = WasmPack::test("test-crate")
+            .browser(Browser::Firefox)
+            .args()
+            .unwrap();
+        assert_eq!(args, vec!["test", "--firefox", "test-crate"]);
+    }
+
+    #[test]
+    fn test_environment_is_required() {
+        let result = WasmPack::test("test-crate").args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+
+        let result = WasmPack::test("test-crate")
+            .node(true)
+            .headless(true)
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(reason)) if reason.contains("headless")
+        ));
+
+        let args = WasmPack::test("test-crate")
+            .raw_args(["--chrome"])
+            .headless(true)
+            .args()
+            .unwrap();
+        assert_eq!(args, vec!["test", "--headless", "--chrome", "test-crate"]);
+    }
+
+    #[test]
+    fn other_subcommands_args() {
+        let args = WasmPack::test("test-crate")
+            .raw_args(["--node"])
+            .raw_cargo_arg("--locked")
+            .args()
+            .unwrap();
+        assert_eq!(args, vec!["test", "--node", "test-crate", "--", "--locked"]);
+
+        let args = WasmPack::pack("test-crate").args().unwrap();
+        assert_eq!(args, vec!["pack", "test-crate"]);
+
+        let args = WasmPack::version()
+            .wasm_pack_log_level(log::Level::Error)
+            .args()
+            .unwrap();
+        assert_eq!(args, vec!["--log-level", "error", "--version"]);
+    }
+
+    #[test]
+    fn checking_the_version() {
+        let output = WasmPack::version()
+            .run()
+            .expect("Couldn't run `wasm-pack --version`.");
+        let version = output.version().expect("Couldn't parse the version.");
+        assert_eq!(version.to_string(), crate::WASM_PACK_VERSION);
+    }
+
+    #[test]
+    fn building_with_a_profile() {
+        let built_crate_path = "target/built-test-crate-dev";
+        let output = WasmPack::build("test-crate")
+            .profile(BuildProfile::Dev)
+            .out_dir(format!("../{}", built_crate_path))
+            .run()
+            .expect("Couldn't run `wasm-pack`.");
+
+        assert_eq!(output.profile(), BuildProfile::Dev);
+        assert!(output.out_dir().ends_with(built_crate_path));
+        assert_eq!(output.output_name().as_deref(), Some("test_crate"));
+        let entry_js =
+            std::fs::read_to_string(Path::new(built_crate_path).join("test_crate.js")).unwrap();
+        assert!(entry_js.contains(&crate::expected_entry_js(
+            Target::Bundler,
+            "test-crate",
+            None
+        )));
+
+        // Dev builds never run wasm-opt.
+        assert!(!output.wasm_opt_ran());
+
+        // wasm-bindgen strips DWARF unless the crate asks to keep it.
+        assert!(!output.has_debug_info().unwrap());
+        assert!(Path::new(built_crate_path)
+            .join("test_crate_bg.wasm")
+            .exists());
+
+        let _ignore_errors = std::fs::remove_dir_all(built_crate_path);
+    }
+
+    #[derive(Debug)]
+    #[cfg(unix)]
+    struct FailingRunner;
+
+    #[cfg(unix)]
+    impl CommandRunner for FailingRunner {
+        fn run(&self, invocation: &crate::Invocation) -> std::io::Result<std::process::Output> {
+            use std::os::unix::process::ExitStatusExt;
+
+            let env_vars = invocation.env_vars().collect::<Vec<_>>();
+            Ok(std::process::Output {
+                status: std::process::ExitStatus::from_raw(1 << 8),
+                stdout: format!("{:?}", env_vars).into_bytes(),
+                stderr: format!("{:?}", invocation.args()).into_bytes(),
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    struct ExecutablePathRunner;
+
+    impl CommandRunner for ExecutablePathRunner {
+        fn run(&self, invocation: &crate::Invocation) -> std::io::Result<std::process::Output> {
+            Ok(std::process::Output {
+                status: std::process::ExitStatus::default(),
+                stdout: invocation.executable().display().to_string().into_bytes(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn deterministic_temp_names() {
+        let wasm_pack = WasmPack::build("test-crate")
+            .command_runner(ExecutablePathRunner)
+            .temp_name_suffix(|| "snapshot".to_string());
+        let planned_path = wasm_pack
+            .planned_temp_path(crate::Platform::current().unwrap())
+            .unwrap();
+        let output = wasm_pack.run().unwrap();
+        assert_eq!(Path::new(output.stdout()), planned_path);
+        assert!(!planned_path.exists());
+
+        let file_name = format!(
+            "wasm-pack-{}-v{}-snapshot",
+            crate::Platform::current().unwrap(),
+            crate::CRATE_VERSION
+        );
+        assert!(Path::new(output.stdout()).ends_with(Path::new("target").join(file_name)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn choosing_the_executable_mode() {
+        let output = WasmPack::login()
+            .command_runner(ExecutablePathRunner)
+            .executable_mode(0o700)
+            .run()
+            .unwrap();
+        assert_eq!(output.executable_permissions(), Some(0o700));
+
+        let result = WasmPack::login().executable_mode(0o644).run();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn seeing_the_command_before_it_runs() {
+        let commands = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let output = WasmPack::build("test-crate")
+            .command_runner(ExecutablePathRunner)
+            .raw_args(["--registry-token=hunter2"])
+            .on_command({
+                let commands = commands.clone();
+                move |executable, args| {
+                    let command = (executable.to_path_buf(), args.to_vec());
+                    commands.lock().unwrap().push(command);
+                }
+            })
+            .run()
+            .unwrap();
+
+        let commands = commands.lock().unwrap();
+        assert_eq!(commands.len(), 1);
+        let (executable, args) = &commands[0];
+        assert_eq!(executable, Path::new(output.stdout()));
+        assert_eq!(
+            args,
+            &["build", "--registry-token=[redacted]", "test-crate"]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn substituting_the_command_runner() {
+        let result = WasmPack::build("test-crate")
+            .command_runner(FailingRunner)
+            .run();
+
+        if let Err(WasmPackError::WasmPackReturnedAnError { stdout, stderr, .. }) = result {
+            assert_eq!(stdout, "[]");
+            assert_eq!(stderr, r#"["build", "test-crate"]"#);
+        } else {
+            panic!("Expected WasmPackReturnedAnError error, got {:?}", result);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn long_output_is_truncated() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/long-output-test");
+        let contents = "#!/bin/sh\nhead -c 100000 /dev/zero | tr '\\0' a\necho done >&2\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .max_output_bytes(1000)
+            .run()
+            .expect("Couldn't run script.");
+        assert!(output.was_truncated());
+        assert!(output.stdout().len() < 1100);
+        assert!(output.stdout().starts_with("aaaa"));
+        assert!(output.stdout().contains("truncated"));
+        assert_eq!(output.stderr(), "done");
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn profile_survives_args_transforms() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/profile-transform-test");
+        let script = fake_wasm_pack(
+            dir,
+            "#!/bin/sh
+exit 0
+",
+        );
+
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .profile(BuildProfile::Dev)
+            .transform_args(|args| {
+                let mut wrapped = vec![OsString::from("wrapper")];
+                wrapped.extend(args);
+                wrapped
+            })
+            .run()
+            .expect("Couldn't run script.");
+        assert_eq!(output.profile(), BuildProfile::Dev);
+        assert!(output.out_dir().ends_with("test-crate/pkg"));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn deleting_ephemeral_out_dirs() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/ephemeral-test");
+        let out_dir = dir.join("pkg");
+        let contents = format!(
+            "#!/bin/sh\nmkdir -p {0}\ntouch {0}/test_crate.js\n",
+            out_dir.display()
+        );
+        let script = fake_wasm_pack(dir, &contents);
+
+        let wasm_pack = WasmPack::build("test-crate")
+            .executable(&script)
+            .out_dir_from_current_dir(&out_dir)
+            .ephemeral(true);
+        let output = wasm_pack.run().expect("Couldn't run script.");
+        assert!(output.generated_files()[0].ends_with("pkg/test_crate.js"));
+        drop(output);
+        assert!(!out_dir.exists());
+
+        let mut output = wasm_pack.run().expect("Couldn't run script.");
+        output.persist();
+        drop(output);
+        assert!(out_dir.exists());
+
+        // Now that it exists, it isn't the build's to delete.
+        drop(wasm_pack.run().expect("Couldn't run script."));
+        assert!(out_dir.join("test_crate.js").exists());
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn sending_output_to_sinks() {
+        use crate::test_support::fake_wasm_pack;
+
+        use crate::sink;
+
+        let dir = Path::new("target/output-sink-test");
+        let contents = "#!/bin/sh\necho compiled\necho 'warning: unused' >&2\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        let log = dir.join("stderr.log");
+        let chunks = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .output_sink(
+                OutputStream::Stdout,
+                sink::Callback::new({
+                    let chunks = chunks.clone();
+                    move |bytes| chunks.lock().unwrap().extend_from_slice(bytes)
+                }),
+            )
+            .output_sink(
+                OutputStream::Stderr,
+                sink::Tee::new(sink::InMemory, sink::File::create(&log).unwrap()),
+            )
+            .run()
+            .expect("Couldn't run script.");
+
+        assert_eq!(output.stdout(), "");
+        assert_eq!(*chunks.lock().unwrap(), b"compiled\n");
+        assert_eq!(output.stderr(), "warning: unused");
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "warning: unused\n");
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn inheriting_and_capturing_output() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/inherit-and-capture-test");
+        let contents = "#!/bin/sh\necho '[inherit_and_capture test] stdout'\necho '[inherit_and_capture test] stderr' >&2\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        let output = WasmPack::login()
+            .executable(&script)
+            .inherit_and_capture(true)
+            .run()
+            .expect("Couldn't run script.");
+        assert_eq!(output.stdout(), "[inherit_and_capture test] stdout");
+        assert_eq!(output.stderr(), "[inherit_and_capture test] stderr");
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reporting_progress() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/progress-test");
+        let contents = "#!/bin/sh\necho \"$CARGO_TERM_PROGRESS_WHEN\"\nprintf '    Building [=>  ] 1/2: a\\r   Compiling b v1.0.0\\n    Building [==> ] 3/2: b\\r' >&2\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        let fractions = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .on_progress({
+                let fractions = fractions.clone();
+                move |fraction| fractions.lock().unwrap().push(fraction)
+            })
+            .run()
+            .expect("Couldn't run script.");
+        assert_eq!(output.stdout(), "always");
+        assert_eq!(
+            output.stderr(),
+            "   Compiling b v1.0.0\n    Building [==> ] 3/2: b"
+        );
+        assert_eq!(*fractions.lock().unwrap(), [0.5, 1.0]);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn limiting_memory() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/memory-limit-test");
+        let contents = "#!/bin/sh\nulimit -v\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .memory_limit(1 << 30)
+            .run()
+            .expect("Couldn't run script.");
+        // `ulimit -v` is in kibibytes.
+        assert_eq!(output.stdout(), (1 << 20).to_string());
+
+        let contents =
+            "#!/bin/sh\necho 'memory allocation of 4096 bytes failed' >&2\nkill -ABRT $$\n";
+        std::fs::write(&script, contents).unwrap();
+        let result = WasmPack::build("test-crate")
+            .executable(&script)
+            .memory_limit(1 << 30)
+            .run();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::MemoryLimitExceeded { limit, .. }) if limit == 1 << 30
+        ));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn streaming_output_lines() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/streaming-output-test");
+        let contents = "#!/bin/sh\nfor i in 1 2 3 4 5; do echo out $i; echo err $i >&2; done\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        // The streams are read on separate threads, so only the order within
+        // each stream is predictable.
+        let recent_lines = RecentLines::new(6);
+        WasmPack::build("test-crate")
+            .executable(&script)
+            .stream_into(recent_lines.clone())
+            .run()
+            .expect("Couldn't run script.");
+
+        assert_eq!(recent_lines.recent_lines(4).len(), 4);
+        let lines = recent_lines.recent_lines(10);
+        assert_eq!(lines.len(), 6);
+        let last_line_from = |stream| {
+            lines
+                .iter()
+                .rev()
+                .find(|line| line.stream == stream)
+                .map(|line| line.text.as_str())
+        };
+        assert_eq!(last_line_from(crate::OutputStream::Stdout), Some("out 5"));
+        assert_eq!(last_line_from(crate::OutputStream::Stderr), Some("err 5"));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn writing_build_info() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/build-info-test");
+        let out_dir = dir.join("pkg");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let script = fake_wasm_pack(dir, "#!/bin/sh\necho wasm-pack 0.13.0\n");
+
+        WasmPack::build("test-crate")
+            .executable(&script)
+            .out_dir_from_current_dir(&out_dir)
+            .raw_cargo_arg("--config=registries.private.token=hunter2")
+            .write_build_info(true)
+            .run()
+            .expect("Couldn't run script.");
+
+        let build_info = std::fs::read(out_dir.join("build-info.json")).unwrap();
+        let build_info: serde_json::Value = serde_json::from_slice(&build_info).unwrap();
+        assert_eq!(build_info["wasm_pack_version"], "0.13.0");
+        assert!(build_info["timestamp"].as_u64().unwrap() > 0);
+        let argv = build_info["argv"].as_array().unwrap();
+        assert_eq!(argv[0], "build");
+        assert_eq!(
+            argv.last().unwrap(),
+            "--config=registries.private.token=[redacted]"
+        );
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn wasm_size_budgets() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/size-budget-test");
+        let out_dir = dir.join("pkg");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let contents = format!(
+            "#!/bin/sh\nhead -c 1000 /dev/zero > {}\n",
+            out_dir.join("test_crate_bg.wasm").display()
+        );
+        let script = fake_wasm_pack(dir, &contents);
+        let build = WasmPack::build("test-crate")
+            .executable(&script)
+            .out_dir_from_current_dir(&out_dir);
+
+        let output = build.clone().wasm_size_budget(1000).run().unwrap();
+        assert_eq!(output.wasm_size(), 1000);
+
+        let result = build.clone().wasm_size_budget(999).run();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::BudgetExceeded {
+                actual: 1000,
+                budget: 999,
+                gzipped: false
+            })
+        ));
+
+        // A kilobyte of zeroes compresses well.
+        build.clone().gzipped_wasm_size_budget(100).run().unwrap();
+        let result = build.gzipped_wasm_size_budget(1).run();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::BudgetExceeded { gzipped: true, .. })
+        ));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn feeding_stdin() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/stdin-test");
+        let script = fake_wasm_pack(dir, "#!/bin/sh\necho \"$@\" >&2\ncat\n");
+
+        let output = WasmPack::login()
+            .executable(&script)
+            .stdin("hunter2\n")
+            .run()
+            .expect("Couldn't run script.");
+        assert_eq!(output.stderr(), "login");
+        assert_eq!(output.stdout(), "hunter2");
+
+        // Without any, stdin is empty rather than inherited.
+        let output = WasmPack::login()
+            .executable(&script)
+            .run()
+            .expect("Couldn't run script.");
+        assert_eq!(output.stdout(), "");
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn generated_files_leave_out_leftovers() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/leftovers-test");
+        let out_dir = dir.join("pkg");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        let leftover = out_dir.join("other_crate_bg.wasm");
+        std::fs::write(&leftover, b"").unwrap();
+        let long_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(&leftover)
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+
+        let contents = format!("#!/bin/sh\necho > {}/test_crate.js\n", out_dir.display());
+        let script = fake_wasm_pack(dir, &contents);
+
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .out_dir_from_current_dir(&out_dir)
+            .run()
+            .expect("Couldn't run script.");
+        let names = output
+            .generated_files()
+            .iter()
+            .map(|path| path.file_name().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["test_crate.js"]);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn gzipping_leaves_out_leftovers() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/gzip-leftovers-test");
+        let out_dir = dir.join("pkg");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        let leftover = out_dir.join("other_crate_bg.wasm");
+        std::fs::write(&leftover, vec![7u8; 1000]).unwrap();
+        let long_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(&leftover)
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+
+        let contents = format!(
+            "#!/bin/sh
+head -c 1000 /dev/zero > {}
+",
+            out_dir.join("test_crate_bg.wasm").display()
+        );
+        let script = fake_wasm_pack(dir, &contents);
+
+        let output = WasmPack::build("test-crate")
+            .executable(&script)
+            .out_dir_from_current_dir(&out_dir)
+            .gzip_output(true)
+            .run()
+            .expect("Couldn't run script.");
+        let gzipped = output
+            .gzipped_wasm()
+            .iter()
+            .map(|gzipped| gzipped.path().file_name().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(gzipped, ["test_crate_bg.wasm.gz"]);
+        assert!(!out_dir.join("other_crate_bg.wasm.gz").exists());
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn counting_imports_in_invalid_wasm() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/invalid-wasm-test");
+        let out_dir = dir.join("pkg");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let contents = format!(
+            "#!/bin/sh
+echo 'not wasm' > {}
+",
+            out_dir.join("test_crate_bg.wasm").display()
+        );
+        let script = fake_wasm_pack(dir, &contents);
+
+        let result = WasmPack::build("test-crate")
+            .executable(&script)
+            .out_dir_from_current_dir(&out_dir)
+            .max_imports(10)
+            .run();
+        match result {
+            Err(error @ WasmPackError::CouldntParseWasm(_)) => {
+                assert_eq!(error.kind(), crate::WasmPackErrorKind::CouldntParseWasm);
+                assert!(error.to_string().contains("test_crate_bg.wasm"));
+            }
+            result => panic!("Expected CouldntParseWasm error, got {:?}", result),
+        }
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn jobs_are_passed_through_the_environment() {
+        let result = WasmPack::build("test-crate")
+            .jobs(2)
+            .command_runner(FailingRunner)
+            .run();
+
+        if let Err(WasmPackError::WasmPackReturnedAnError { stdout, .. }) = result {
+            assert_eq!(stdout, r#"[("CARGO_BUILD_JOBS", "2")]"#);
+        } else {
+            panic!("Expected WasmPackReturnedAnError error, got {:?}", result);
+        }
+
+        let result = WasmPack::build("test-crate").jobs(0).args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn wasm_rustflags_are_only_for_the_wasm_target() {
+        let options = WasmPack::build("test-crate")
+            .wasm_rustflags("-C target-feature=+simd128")
+            .run_options()
+            .unwrap();
+        assert_eq!(
+            options.env,
+            [(
+                "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUSTFLAGS".into(),
+                "-C target-feature=+simd128".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn writing_a_shell_script() {
+        let script = WasmPack::build("test-crate")
+            .profile(BuildProfile::Dev)
+            .out_dir("../target/my pkg")
+            .jobs(2)
+            .to_shell_script()
+            .unwrap();
+        assert_eq!(
+            script,
+            "#!/bin/sh\nset -e\nexport CARGO_BUILD_JOBS=2\nwasm-pack build --dev --out-dir '../target/my pkg' test-crate\n"
+        );
+    }
+
+    #[test]
+    fn prepending_to_the_path() {
+        let options = WasmPack::build("test-crate")
+            .prepend_path("/first")
+            .prepend_path("/second")
+            .run_options()
+            .unwrap();
+
+        let (name, value) = options.env.last().unwrap();
+        assert_eq!(name, "PATH");
+        let dirs = std::env::split_paths(value).collect::<Vec<_>>();
+        assert_eq!(
+            dirs[..2],
+            [PathBuf::from("/first"), PathBuf::from("/second")]
+        );
+        assert!(dirs.len() > 2);
+    }
+
+    #[test]
+    fn using_a_tool_cache_dir() {
+        let dir = Path::new("target/tool-cache-test");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+
+        let options = WasmPack::build("test-crate")
+            .tool_cache_dir(dir)
+            .run_options()
+            .unwrap();
+        assert!(dir.is_dir());
+        assert!(options
+            .env
+            .contains(&("WASM_PACK_CACHE".into(), dir.into())));
+
+        // A directory can't be created inside a file.
+        let result = WasmPack::build("test-crate")
+            .tool_cache_dir("Cargo.toml/tool-cache")
+            .run_options();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::CouldntUseToolCacheDir(_))
+        ));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn using_a_cargo_config_dir() {
+        let dir = Path::new("target/cargo-config-dir-test");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+
+        let build = WasmPack::build("test-crate").cargo_config_dir(dir);
+        let result = build.run_options();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+
+        let config = "[source.crates-io]\nreplace-with = \"vendored-sources\"\n";
+        std::fs::write(dir.join("config.toml"), config).unwrap();
+        let options = build.run_options().unwrap();
+        let cargo_home = std::env::current_dir().unwrap().join(dir);
+        assert!(options
+            .env
+            .contains(&("CARGO_HOME".into(), cargo_home.into())));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn building_for_the_web() {
+        let built_crate_path = "target/built-test-crate-web";
+        WasmPack::build("test-crate")
+            .profile(BuildProfile::Dev)
+            .target(Target::Web)
+            .out_dir(format!("../{}", built_crate_path))
+            .validate_output(true)
+            .run()
+            .expect("Couldn't build for the web.");
+
+        let entry_js =
+            std::fs::read_to_string(Path::new(built_crate_path).join("test_crate.js")).unwrap();
+        assert!(entry_js.contains(&crate::expected_entry_js(Target::Web, "test-crate", None)));
+
+        let _ignore_errors = std::fs::remove_dir_all(built_crate_path);
+    }
+
+    #[test]
+    fn building_for_node() {
+        let built_crate_path = "target/built-test-crate-node";
+        let output = WasmPack::build("test-crate")
+            .profile(BuildProfile::Dev)
+            .target(Target::NodeJs)
+            .out_dir(format!("../{}", built_crate_path))
+            .raw_args(["--out-name", "app"])
+            .run()
+            .expect("Couldn't build for Node.js.");
+        assert_eq!(output.output_name().as_deref(), Some("app"));
+
+        let entry_js = std::fs::read_to_string(Path::new(built_crate_path).join("app.js")).unwrap();
+        let expected = crate::expected_entry_js(Target::NodeJs, "test-crate", Some("app"));
+        assert!(entry_js.contains(&expected));
+
+        let _ignore_errors = std::fs::remove_dir_all(built_crate_path);
+    }
+
+    #[test]
+    fn denying_warnings() {
+        let built_crate_path = "target/built-test-crate-deny-warnings";
+        let result = WasmPack::build("test-crate")
+            .profile(BuildProfile::Dev)
+            .out_dir(format!("../{}", built_crate_path))
+            .deny_warnings(true)
+            .run();
+
+        // The test crate has an unused function.
+        if let Err(WasmPackError::WarningsDenied { warnings }) = &result {
+            assert!(warnings
+                .iter()
+                .any(|warning| warning.contains("`set_panic_hook` is never used")));
+        } else {
+            panic!("Expected WarningsDenied error, got {:?}", result);
+        }
+
+        let _ignore_errors = std::fs::remove_dir_all(built_crate_path);
+    }
+
+    #[test]
+    fn workspace_member_resolves_to_its_directory() {
+        let args = WasmPack::build("test-crate")
+            .workspace_member("test-crate")
+            .args()
+            .unwrap();
+
+        let input_crate = PathBuf::from(args.last().unwrap());
+        assert!(input_crate.is_absolute());
+        assert!(input_crate.ends_with("test-crate"));
+    }
+
+    #[test]
+    fn manifest_path_resolves_to_its_directory() {
+        let args = WasmPack::build("ignored")
+            .manifest_path("test-crate/Cargo.toml")
+            .args()
+            .unwrap();
+        assert_eq!(args.last().unwrap(), "test-crate");
+
+        let args = WasmPack::build("ignored")
+            .manifest_path("Cargo.toml")
+            .args()
+            .unwrap();
+        assert_eq!(args.last().unwrap(), ".");
+    }
+
+    #[test]
+    fn invalid_manifest_paths() {
+        let result = WasmPack::build("ignored")
+            .manifest_path("test-crate/Cargo.lock")
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::ManifestPathIsntCargoToml { .. })
+        ));
+
+        let result = WasmPack::build("ignored")
+            .manifest_path("fake-crate/Cargo.toml")
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::ManifestNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn args_are_grouped_by_who_they_are_for() {
+        let args = WasmPack::build("test-crate")
+            .raw_cargo_arg("--features")
+            .raw_wasm_pack_arg("--no-typescript")
+            .out_dir("../target/pkg")
+            .raw_cargo_arg("console_error_panic_hook")
+            .no_install(true)
+            .raw_wasm_pack_arg("--weak-refs")
+            .args()
+            .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "--mode",
+                "no-install",
+                "--out-dir",
+                "../target/pkg",
+                "--no-typescript",
+                "--weak-refs",
+                "test-crate",
+                "--",
+                "--features",
+                "console_error_panic_hook",
+            ]
+        );
+    }
+
+    #[test]
+    fn feature_flags_go_to_cargo() {
+        let args = WasmPack::build("test-crate")
+            .raw_cargo_arg("--locked")
+            .features(["console_error_panic_hook"])
+            .no_default_features(true)
+            .features(["serde"])
+            .raw_wasm_pack_arg("--no-typescript")
+            .args()
+            .unwrap();
+
+        // Anything before the `--` would be taken by wasm-pack instead.
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "--no-typescript",
+                "test-crate",
+                "--",
+                "--features",
+                "console_error_panic_hook,serde",
+                "--no-default-features",
+                "--locked",
+            ]
+        );
+
+        let args = WasmPack::test("test-crate")
+            .node(true)
+            .all_features(true)
+            .args()
+            .unwrap();
+        assert_eq!(
+            args,
+            vec!["test", "--node", "test-crate", "--", "--all-features"]
+        );
+
+        for arg in ["--features", "--features=serde", "-F", "--all-features"] {
+            let result = WasmPack::build("test-crate").raw_wasm_pack_arg(arg).args();
+            assert!(
+                matches!(result, Err(WasmPackError::InvalidConfiguration(_))),
+                "{}",
+                arg
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reading_cargo_messages() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/message-format-test");
+        let message = r#"{"reason":"compiler-message","package_id":"test-crate","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[],"rendered":null}}"#;
+        let contents = format!(
+            "#!/bin/sh\necho '{}'\necho '[INFO]: Compiling to Wasm...'\nexit 1\n",
+            message
+        );
+        let script = fake_wasm_pack(dir, &contents);
+
+        let build = WasmPack::build("test-crate").message_format_json(true);
+        assert_eq!(
+            build.args().unwrap(),
+            vec!["build", "test-crate", "--", "--message-format=json"]
+        );
+
+        let error = build.executable(&script).run().unwrap_err();
+        let messages = error.cargo_messages();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].is_error());
+        assert_eq!(messages[0].message, "mismatched types");
+        assert_eq!(messages[0].code.as_deref(), Some("E0308"));
+
+        let result = WasmPack::build("test-crate")
+            .message_format_json(true)
+            .raw_cargo_arg("--message-format=short")
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn wasm_pack_log_levels() {
+        let args = WasmPack::build("test-crate")
+            .wasm_pack_log_level(log::Level::Warn)
+            .args()
+            .unwrap();
+        assert_eq!(args, vec!["--log-level", "warn", "build", "test-crate"]);
+
+        let builder = WasmPack::build("test-crate").wasm_pack_log_level(log::Level::Debug);
+        assert_eq!(
+            builder.args().unwrap(),
+            vec!["--log-level", "info", "build", "test-crate"]
+        );
+        let env = builder.run_options().unwrap().env;
+        assert!(env.contains(&("RUST_LOG".into(), "wasm_pack=debug".into())));
+    }
+
+    #[test]
+    fn quieting_wasm_pack() {
+        let args = WasmPack::version()
+            .wasm_pack_quiet(true)
+            .wasm_pack_log_level(log::Level::Warn)
+            .args()
+            .unwrap();
+        assert_eq!(args, vec!["--quiet", "--log-level", "warn", "--version"]);
+
+        let out_dir = Path::new("target/quiet-test");
+        let build = |quiet| {
+            WasmPack::build("test-crate")
+                .profile(BuildProfile::Dev)
+                .out_dir_from_current_dir(out_dir)
+                .wasm_pack_quiet(quiet)
+                .raw_cargo_arg("--offline")
+                .run()
+                .expect("Couldn't build test-crate.")
+        };
+
+        // wasm-pack prints its progress messages to stderr.
+        let loud = build(false);
+        let quiet = build(true);
+        assert!(loud.stderr().contains("[INFO]"));
+        assert!(!quiet.stderr().contains("[INFO]"));
+        assert!(
+            quiet.stdout().len() + quiet.stderr().len() < loud.stdout().len() + loud.stderr().len()
+        );
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+    }
+
+    #[test]
+    fn raw_args_go_after_typed_ones() {
+        let args = WasmPack::build("test-crate")
+            .raw_args(["--no-pack", "--weak-refs"])
+            .target(Target::Web)
+            .profile(BuildProfile::Dev)
+            .raw_cargo_arg("--locked")
+            .args()
+            .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "--dev",
+                "--target",
+                "web",
+                "--no-pack",
+                "--weak-refs",
+                "test-crate",
+                "--",
+                "--locked",
+            ]
+        );
+    }
+
+    #[test]
+    fn transforming_args() {
+        let args = WasmPack::build("test-crate")
+            .raw_cargo_arg("--offline")
+            .transform_args(|mut args| {
+                args.push("--locked".into());
+                args
+            })
+            .transform_args(|args| args.into_iter().filter(|arg| arg != "--offline").collect())
+            .args()
+            .unwrap();
+
+        assert_eq!(args, vec!["build", "test-crate", "--", "--locked"]);
+    }
+
+    #[test]
+    fn passthrough_args_share_one_separator() {
+        let args = WasmPack::build("test-crate")
+            .passthrough(["--", "--locked"])
+            .raw_cargo_arg("--offline")
+            .passthrough(["--features", "console_error_panic_hook"])
+            .passthrough(["--"])
+            .args()
+            .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "test-crate",
+                "--",
+                "--locked",
+                "--offline",
+                "--features",
+                "console_error_panic_hook",
+            ]
+        );
+        assert_eq!(args.iter().filter(|arg| *arg == "--").count(), 1);
+    }
+
+    #[test]
+    fn out_dir_from_current_dir_args() {
+        let out_dir_arg = |out_dir| {
+            let args = WasmPack::build("test-crate")
+                .out_dir_from_current_dir(out_dir)
+                .args()
+                .unwrap();
+            args[2].clone()
+        };
+
+        assert_eq!(
+            out_dir_arg("target/built-test-crate"),
+            "../target/built-test-crate"
+        );
+        assert_eq!(out_dir_arg("test-crate"), ".");
+        assert_eq!(out_dir_arg("./test-crate/pkg"), "pkg");
+    }
+
+    #[test]
+    fn out_dir_relative_to_args() {
+        let args = WasmPack::build("test-crate")
+            .out_dir_relative_to(std::env::current_dir().unwrap(), "target/pkg")
+            .args()
+            .unwrap();
+        assert_eq!(args[2], "../target/pkg");
+    }
+
+    #[test]
+    fn wasm_opt_args_are_unsupported() {
+        let result = WasmPack::build("test-crate")
+            .optimization_level(OptLevel::MinSize)
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedOption { option, reason })
+                if option == "wasm_opt_args" && reason.contains(r#"["-Oz"]"#)
+        ));
+    }
+
+    #[test]
+    fn no_opt_is_unsupported() {
+        let result = WasmPack::build("test-crate").no_opt(true).args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedOption { option, reason })
+                if option == "no_opt" && reason.contains("wasm-opt = false")
+        ));
+
+        let result = WasmPack::build("test-crate")
+            .no_opt(true)
+            .optimization_level(OptLevel::Size)
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+
+        assert!(WasmPack::build("test-crate").no_opt(false).args().is_ok());
+    }
+
+    #[test]
+    fn cargo_profile_is_unsupported() {
+        let result = WasmPack::build("test-crate").cargo_profile("wasm").args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedOption { option, reason })
+                if option == "cargo_profile" && reason.contains("`wasm`")
+        ));
+    }
+
+    #[test]
+    fn gzip_level_must_be_in_range() {
+        assert!(WasmPack::build("test-crate").gzip_level(9).args().is_ok());
+
+        let result = WasmPack::build("test-crate").gzip_level(10).args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(reason)) if reason.contains("0 to 9")
+        ));
+    }
+
+    #[test]
+    fn gzipped_budget_needs_gzipping() {
+        let result = WasmPack::build("test-crate")
+            .gzipped_wasm_size_budget(1000)
+            .gzip_output(false)
+            .args();
+
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(reason)) if reason.contains("gzip_output")
+        ));
+    }
+
+    #[test]
+    fn building_for_no_modules() {
+        let built_crate_path = "target/built-test-crate-no-modules";
+        let output = WasmPack::build("test-crate")
+            .profile(BuildProfile::Dev)
+            .target(Target::NoModules)
+            .no_modules_global("wasm_bindgen")
+            .out_dir(format!("../{}", built_crate_path))
+            .validate_output(true)
+            .run()
+            .expect("Couldn't build for no-modules.");
+        assert!(output
+            .generated_files()
+            .iter()
+            .any(|path| path.ends_with("test_crate.js")));
+
+        let _ignore_errors = std::fs::remove_dir_all(built_crate_path);
+    }
+
+    #[test]
+    fn no_modules_global_needs_no_modules() {
+        let result = WasmPack::build("test-crate")
+            .target(Target::Web)
+            .no_modules_global("wasm_bindgen")
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(_))
+        ));
+
+        let result = WasmPack::build("test-crate")
+            .target(Target::NoModules)
+            .no_modules_global("my_crate")
+            .args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedOption { option, reason })
+                if option == "no_modules_global" && reason.contains("window.my_crate = wasm_bindgen;")
+        ));
+    }
+
+    #[test]
+    fn debug_info_is_unsupported() {
+        let result = WasmPack::build("test-crate").debug_info(false).args();
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedOption { option, reason })
+                if option == "debug_info" && reason.contains("dwarf-debug-info = false")
+        ));
+    }
+
+    #[test]
+    fn no_install_args() {
+        let args = WasmPack::build("test-crate")
+            .no_install(true)
+            .out_dir("../target/pkg")
+            .args()
+            .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                "build",
+                "--mode",
+                "no-install",
+                "--out-dir",
+                "../target/pkg",
+                "test-crate"
+            ]
+        );
+    }
+
+    #[test]
+    fn finding_executables_on_the_path() {
+        let cargo = PathBuf::from(std::env::var_os("CARGO").unwrap());
+        let path =
+            std::env::join_paths([Path::new("/nonexistent"), cargo.parent().unwrap()]).unwrap();
+
+        assert_eq!(find_on_path("cargo", &path), Some(cargo));
+        assert_eq!(
+            find_on_path("wasm-bindgen", OsStr::new("/nonexistent")),
+            None
+        );
+    }
+
+    #[test]
+    fn out_dir_lint() {
+        assert!(out_dir_is_probably_a_mistake(Path::new("target/pkg")));
+        assert!(out_dir_is_probably_a_mistake(Path::new("./pkg")));
+        assert!(!out_dir_is_probably_a_mistake(Path::new("../target/pkg")));
+        assert!(!out_dir_is_probably_a_mistake(Path::new("/tmp/pkg")));
+        assert!(!out_dir_is_probably_a_mistake(Path::new(".")));
+        assert!(!out_dir_is_probably_a_mistake(Path::new("./")));
+    }
+
+    #[test]
+    fn out_dir_warning_names_the_resolved_crate() {
+        let build = WasmPack::build("elsewhere")
+            .manifest_path("test-crate/Cargo.toml")
+            .out_dir("target/pkg");
+        let input_crate = build.resolve_input_crate().unwrap();
+        let warning = build.out_dir_warning(&input_crate).unwrap();
+        assert!(
+            warning.contains(r#""test-crate/target/pkg""#),
+            "{}",
+            warning
+        );
+
+        let build = WasmPack::build("test-crate").out_dir(".");
+        assert_eq!(build.out_dir_warning(Path::new("test-crate")), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+/// Put in place of the bytes that were dropped from truncated output.
+const TRUNCATION_MARKER: &[u8] = b"\n[... output truncated by lib_wasm_pack ...]\n";
+
+/// Limit `bytes` to `limit`, keeping the start and the end, since that's where
+/// the interesting parts of build output usually are.
+///
+/// Truncated output is longer than `limit` because of the marker, which is how
+/// [`is_truncated`] tells. Capping output that's already capped doesn't change
+/// it, so it's fine for both a runner and us to do it.
+pub(crate) fn cap(bytes: Vec<u8>, limit: Option<usize>) -> Vec<u8> {
+    let limit = match limit {
+        Some(limit) if bytes.len() > limit => limit,
+        _ => return bytes,
+    };
+
+    let (head_len, tail_len) = split(limit);
+    let mut capped = Vec::with_capacity(limit + TRUNCATION_MARKER.len());
+    capped.extend_from_slice(&bytes[..head_len]);
+    capped.extend_from_slice(TRUNCATION_MARKER);
+    capped.extend_from_slice(&bytes[bytes.len() - tail_len..]);
+    capped
+}
+
+pub(crate) fn is_truncated(bytes: &[u8], limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| bytes.len() > limit)
+}
+
+/// Read `reader` to the end, like [`cap`] but without ever holding more than
+/// `limit` bytes.
+pub(crate) fn read_capped(mut reader: impl Read, limit: Option<usize>) -> io::Result<Vec<u8>> {
+    let Some(limit) = limit else {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        return Ok(bytes);
+    };
+
+    let (head_len, tail_len) = split(limit);
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = VecDeque::with_capacity(tail_len);
+    let mut truncated = false;
+    let mut chunk = [0; 8192];
+
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+
+        let mut rest = &chunk[..read];
+        let to_head = rest.len().min(head_len - head.len());
+        head.extend_from_slice(&rest[..to_head]);
+        rest = &rest[to_head..];
+
+        for &byte in rest {
+            if tail.len() == tail_len {
+                truncated = true;
+                if tail.pop_front().is_none() {
+                    continue;
+                }
+            }
+            tail.push_back(byte);
+        }
+    }
+
+    if truncated {
+        head.extend_from_slice(TRUNCATION_MARKER);
+    }
+    head.extend(tail);
+    Ok(head)
+}
+
+/// The last `n` lines of `text`, and how many lines came before them.
+pub(crate) fn tail_lines(text: &str, n: usize) -> (&str, usize) {
+    let line_count = text.lines().count();
+    if line_count <= n {
+        return (text, 0);
+    }
+
+    let omitted = line_count - n;
+    let start = text
+        .match_indices('\n')
+        .nth(omitted - 1)
+        .map_or(text.len(), |(index, _)| index + 1);
+    (&text[start..], omitted)
+}
+
+fn split(limit: usize) -> (usize, usize) {
+    let head_len = limit / 2;
+    (head_len, limit - head_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capping_keeps_the_start_and_end() {
+        let bytes: Vec<u8> = (0..100).collect();
+
+        assert_eq!(cap(bytes.clone(), None), bytes);
+        assert_eq!(cap(bytes.clone(), Some(100)), bytes);
+        assert!(!is_truncated(&bytes, Some(100)));
+
+        let capped = cap(bytes.clone(), Some(10));
+        assert!(capped.starts_with(&[0, 1, 2, 3, 4]));
+        assert!(capped.ends_with(&[95, 96, 97, 98, 99]));
+        assert!(is_truncated(&capped, Some(10)));
+        assert_eq!(cap(capped.clone(), Some(10)), capped);
+    }
+
+    #[test]
+    fn keeping_the_last_lines() {
+        assert_eq!(tail_lines("a\nb\nc", 5), ("a\nb\nc", 0));
+        assert_eq!(tail_lines("a\nb\nc", 3), ("a\nb\nc", 0));
+        assert_eq!(tail_lines("a\nb\nc", 2), ("b\nc", 1));
+        assert_eq!(tail_lines("a\r\nb\r\nc", 1), ("c", 2));
+        assert_eq!(tail_lines("a\nb\nc", 0), ("", 3));
+        assert_eq!(tail_lines("", 0), ("", 0));
+    }
+
+    #[test]
+    fn reading_matches_capping() {
+        let bytes: Vec<u8> = (0..50_000).map(|i| (i % 251) as u8).collect();
+        for limit in [
+            None,
+            Some(0),
+            Some(1),
+            Some(1000),
+            Some(50_000),
+            Some(60_000),
+        ] {
+            let read = read_capped(&bytes[..], limit).unwrap();
+            assert_eq!(read, cap(bytes.clone(), limit), "limit: {:?}", limit);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// A compiler error, warning or other diagnostic from cargo's
+/// `--message-format=json` output, turned on with
+/// [`WasmPack::message_format_json`](crate::WasmPack::message_format_json).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CargoMessage {
+    /// The package the message is about, like
+    /// `path+file:///src/my_crate#0.1.0`.
+    pub package_id: String,
+    /// How serious it is, like `error`, `warning` or `note`.
+    pub level: String,
+    /// The message on its own, like "unused variable: `x`".
+    pub message: String,
+    /// The lint or error code, like `unused_variables` or `E0308`.
+    pub code: Option<String>,
+    /// The code the message points at.
+    pub spans: Vec<CargoMessageSpan>,
+    /// The message as cargo would have printed it, with its notes and the
+    /// code it points at.
+    pub rendered: Option<String>,
+}
+
+impl CargoMessage {
+    /// Whether this is an error, including internal compiler errors.
+    pub fn is_error(&self) -> bool {
+        self.level == "error" || self.level.starts_with("error:")
+    }
+
+    /// The span the message is mainly about, if it points at any code.
+    pub fn primary_span(&self) -> Option<&CargoMessageSpan> {
+        self.spans.iter().find(|span| span.is_primary)
+    }
+}
+
+/// A piece of code a [`CargoMessage`] points at. Lines and columns start at 1,
+/// and the ends are inclusive for lines but not for columns.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[non_exhaustive]
+pub struct CargoMessageSpan {
+    /// The file, relative to the workspace root unless it's outside of it.
+    pub file_name: PathBuf,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    /// Whether this is what the message is about, rather than context.
+    pub is_primary: bool,
+    /// What the message says about this span in particular.
+    pub label: Option<String>,
+}
+
+/// One line of cargo's JSON output. Only the fields we need are read.
+#[derive(Deserialize)]
+struct JsonLine {
+    reason: String,
+    #[serde(default)]
+    package_id: String,
+    message: Option<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct Diagnostic {
+    level: String,
+    message: String,
+    code: Option<DiagnosticCode>,
+    #[serde(default)]
+    spans: Vec<CargoMessageSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticCode {
+    code: String,
+}
+
+/// The compiler messages in `stdout`, skipping the lines that aren't cargo's
+/// JSON, like wasm-pack's own output, and the JSON lines about other things,
+/// like finished artifacts.
+pub(crate) fn parse(stdout: &str) -> Vec<CargoMessage> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<JsonLine>(line).ok())
+        .filter(|line| line.reason == "compiler-message")
+        .filter_map(|line| {
+            let diagnostic = line.message?;
+            Some(CargoMessage {
+                package_id: line.package_id,
+                level: diagnostic.level,
+                message: diagnostic.message,
+                code: diagnostic.code.map(|code| code.code),
+                spans: diagnostic.spans,
+                rendered: diagnostic.rendered,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_compiler_messages() {
+        let stdout = r#"{"reason":"compiler-artifact","package_id":"registry+https://github.com/rust-lang/crates.io-index#cfg-if@1.0.0","filenames":[]}
+{"reason":"compiler-message","package_id":"path+file:///src/my_crate#0.1.0","message":{"rendered":"warning: unused variable: `x`\n","$message_type":"diagnostic","children":[],"code":{"code":"unused_variables","explanation":null},"level":"warning","message":"unused variable: `x`","spans":[{"byte_end":40,"byte_start":39,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":"help: prefix it with an underscore: `_x`","line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[]}]}}
+{"reason":"compiler-message","package_id":"path+file:///src/my_crate#0.1.0","message":{"rendered":"error: aborting due to 1 previous error\n","children":[],"code":null,"level":"error","message":"aborting due to 1 previous error","spans":[]}}
+not json
+{"reason":"build-finished","success":false}
+"#;
+
+        let messages = parse(stdout);
+        assert_eq!(messages.len(), 2);
+
+        let warning = &messages[0];
+        assert_eq!(warning.package_id, "path+file:///src/my_crate#0.1.0");
+        assert_eq!(warning.level, "warning");
+        assert!(!warning.is_error());
+        assert_eq!(warning.code.as_deref(), Some("unused_variables"));
+        assert_eq!(
+            warning.primary_span(),
+            Some(&CargoMessageSpan {
+                file_name: PathBuf::from("src/lib.rs"),
+                line_start: 3,
+                line_end: 3,
+                column_start: 9,
+                column_end: 10,
+                is_primary: true,
+                label: Some("help: prefix it with an underscore: `_x`".to_string()),
+            })
+        );
+
+        let error = &messages[1];
+        assert!(error.is_error());
+        assert_eq!(error.code, None);
+        assert_eq!(error.primary_span(), None);
+        assert_eq!(
+            error.rendered.as_deref(),
+            Some("error: aborting due to 1 previous error\n")
+        );
+
+        assert_eq!(parse("[INFO]: Compiling to Wasm...\n"), Vec::new());
+    }
+}
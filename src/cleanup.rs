@@ -0,0 +1,220 @@
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::Platform;
+
+/// Delete executables left in `target` by runs that never got to clean up
+/// after themselves, like when the machine lost power, if they were last
+/// modified more than `older_than` ago. Returns how many were deleted.
+///
+/// Only files named like the executables extracted for a single run,
+/// `wasm-pack-<platform>-v<version>-<uuid>`, are deleted. The executable kept
+/// by [`WasmPack::cache_executable`](crate::WasmPack::cache_executable) and
+/// anything else in `target` is left alone.
+pub fn cleanup_stale_binaries(older_than: Duration) -> io::Result<usize> {
+    let target_dir = std::env::current_dir()?.join("target");
+    cleanup_stale_binaries_in(&target_dir, older_than)
+}
+
+/// Delete the executables kept in `target` by
+/// [`WasmPack::cache_executable`](crate::WasmPack::cache_executable) for this
+/// version of the crate, for every platform, so the next run extracts a fresh
+/// one. Returns how many were deleted.
+///
+/// Only files named exactly like cached executables,
+/// `wasm-pack-<platform>-v<version>`, are deleted. Executables extracted for
+/// a single run are left to [`cleanup_stale_binaries`], since they may still
+/// be running. On Windows, deleting a cached executable that's running
+/// fails.
+pub fn clear_cache() -> io::Result<usize> {
+    let target_dir = std::env::current_dir()?.join("target");
+    clear_cache_in(&target_dir)
+}
+
+fn clear_cache_in(dir: &Path) -> io::Result<usize> {
+    let mut removed = 0;
+    for platform in Platform::ALL {
+        let path = dir.join(crate::cached_file_name(platform));
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                log::debug!("Deleted cached CLI executable: {:?}", path);
+                removed += 1;
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(removed)
+}
+
+fn cleanup_stale_binaries_in(dir: &Path, older_than: Duration) -> io::Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let is_stale_binary = entry
+            .file_name()
+            .to_str()
+            .is_some_and(is_extracted_binary_name);
+        if !is_stale_binary {
+            continue;
+        }
+
+        let metadata = entry.metadata()?;
+        // Files from the future count as new.
+        let age = now.duration_since(metadata.modified()?).unwrap_or_default();
+        if !metadata.is_file() || age < older_than {
+            continue;
+        }
+
+        match std::fs::remove_file(entry.path()) {
+            Ok(()) => {
+                log::debug!("Deleted stale CLI executable: {:?}", entry.path());
+                removed += 1;
+            }
+            // Another process cleaned it up first.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Whether `name` is `wasm-pack-<platform>-v<version>-<uuid>`.
+fn is_extracted_binary_name(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix("wasm-pack-") else {
+        return false;
+    };
+    let Some(rest) = Platform::ALL.iter().find_map(|platform| {
+        rest.strip_prefix(&platform.to_string())
+            .and_then(|rest| rest.strip_prefix("-v"))
+    }) else {
+        return false;
+    };
+
+    // Versions contain dashes too, so the UUID is found from the end.
+    const UUID_LEN: usize = 36;
+    let Some((version, uuid)) = rest
+        .len()
+        .checked_sub(UUID_LEN)
+        .and_then(|uuid_start| rest.split_at_checked(uuid_start))
+        .and_then(|(version, uuid)| Some((version.strip_suffix('-')?, uuid)))
+    else {
+        return false;
+    };
+
+    !version.is_empty()
+        && version.starts_with(|c: char| c.is_ascii_digit())
+        && uuid.parse::<uuid::Uuid>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_extracted_binary_names() {
+        let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        for platform in Platform::ALL {
+            assert!(is_extracted_binary_name(&format!(
+                "wasm-pack-{}-v0.12.1-0.1.0-{}",
+                platform, uuid
+            )));
+        }
+
+        for name in [
+            // The cached executable.
+            "wasm-pack-x86_64-unknown-linux-musl-v0.12.1-0.1.0",
+            // A partially written cached executable.
+            &format!(
+                "wasm-pack-x86_64-unknown-linux-musl-v0.12.1-0.1.0.{}.partial",
+                uuid
+            ),
+            // A custom suffix.
+            "wasm-pack-x86_64-unknown-linux-musl-v0.12.1-0.1.0-snapshot",
+            "wasm-pack-some-other-platform-v0.12.1-0.1.0-67e55044-10b1-426f-9247-bb680e5fe0c8",
+            &format!("wasm-pack-x86_64-unknown-linux-musl-{}", uuid),
+            "wasm-pack",
+            "debug",
+        ] {
+            assert!(!is_extracted_binary_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn cleaning_up_stale_binaries() {
+        let dir = Path::new("target/cleanup-test");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+
+        let stale = format!(
+            "wasm-pack-{}-v0.12.1-0.1.0-{}",
+            Platform::LinuxX64,
+            uuid::Uuid::new_v4()
+        );
+        let cached = format!("wasm-pack-{}-v0.12.1-0.1.0", Platform::LinuxX64);
+        for name in [stale.as_str(), cached.as_str(), "unrelated"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let removed = cleanup_stale_binaries_in(dir, Duration::from_secs(60 * 60)).unwrap();
+        assert_eq!(removed, 0);
+
+        let removed = cleanup_stale_binaries_in(dir, Duration::ZERO).unwrap();
+        assert_eq!(removed, 1);
+        assert!(!dir.join(&stale).exists());
+        assert!(dir.join(&cached).exists());
+        assert!(dir.join("unrelated").exists());
+
+        let missing_dir = dir.join("missing");
+        assert_eq!(
+            cleanup_stale_binaries_in(&missing_dir, Duration::ZERO).unwrap(),
+            0
+        );
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn clearing_the_cache() {
+        let dir = Path::new("target/clear-cache-test");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+
+        let run_binary = format!(
+            "wasm-pack-{}-v{}-{}",
+            Platform::LinuxX64,
+            crate::CRATE_VERSION,
+            uuid::Uuid::new_v4()
+        );
+        let older_version = format!("wasm-pack-{}-v0.0.1", Platform::LinuxX64);
+        let mut names = vec![run_binary, older_version, "unrelated".to_string()];
+        for platform in [Platform::LinuxX64, Platform::MacOs] {
+            std::fs::write(dir.join(crate::cached_file_name(platform)), b"").unwrap();
+        }
+        for name in &names {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        assert_eq!(clear_cache_in(dir).unwrap(), 2);
+        assert_eq!(clear_cache_in(dir).unwrap(), 0);
+        let mut left = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        left.sort();
+        names.sort();
+        assert_eq!(left, names);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The first difference [`compare_outputs`] found between two out-dirs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputDifference {
+    /// A file exists in both out-dirs, but its contents differ.
+    ContentsDiffer { file_name: OsString },
+    /// A file exists in only one of the out-dirs, `dir`.
+    OnlyIn { dir: PathBuf, file_name: OsString },
+}
+
+/// Whether the `*_bg.wasm` files in two out-dirs are byte-for-byte identical,
+/// like when checking that builds are reproducible.
+pub fn wasm_outputs_match(dir_a: &Path, dir_b: &Path) -> io::Result<bool> {
+    Ok(compare_outputs(dir_a, dir_b, false)?.is_none())
+}
+
+/// Compare the `*_bg.wasm` files in two out-dirs, and the generated JS too if
+/// `include_js` is set, returning the first difference. Files are compared in
+/// name order. `None` means the outputs match.
+pub fn compare_outputs(
+    dir_a: &Path,
+    dir_b: &Path,
+    include_js: bool,
+) -> io::Result<Option<OutputDifference>> {
+    let files_a = compared_file_names(dir_a, include_js)?;
+    let files_b = compared_file_names(dir_b, include_js)?;
+
+    let only_in = |dir: &Path, file_name: &OsString| OutputDifference::OnlyIn {
+        dir: dir.to_path_buf(),
+        file_name: file_name.clone(),
+    };
+    if let Some(file_name) = files_a.difference(&files_b).next() {
+        return Ok(Some(only_in(dir_a, file_name)));
+    }
+    if let Some(file_name) = files_b.difference(&files_a).next() {
+        return Ok(Some(only_in(dir_b, file_name)));
+    }
+
+    for file_name in files_a {
+        let bytes_a = std::fs::read(dir_a.join(&file_name))?;
+        let bytes_b = std::fs::read(dir_b.join(&file_name))?;
+        if bytes_a != bytes_b {
+            return Ok(Some(OutputDifference::ContentsDiffer { file_name }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn compared_file_names(dir: &Path, include_js: bool) -> io::Result<BTreeSet<OsString>> {
+    let mut file_names = BTreeSet::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let file_name = entry?.file_name();
+        let name = file_name.to_string_lossy();
+        if name.ends_with("_bg.wasm") || (include_js && name.ends_with(".js")) {
+            file_names.insert(file_name);
+        }
+    }
+
+    Ok(file_names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparing_outputs() {
+        let dir = Path::new("target/compare-outputs-test");
+        let (dir_a, dir_b) = (dir.join("a"), dir.join("b"));
+        for out_dir in [&dir_a, &dir_b] {
+            std::fs::create_dir_all(out_dir).unwrap();
+            std::fs::write(out_dir.join("app_bg.wasm"), b"\0asm").unwrap();
+            std::fs::write(
+                out_dir.join("app.d.ts"),
+                out_dir.to_string_lossy().as_bytes(),
+            )
+            .unwrap();
+        }
+        std::fs::write(dir_a.join("app.js"), b"a").unwrap();
+        std::fs::write(dir_b.join("app.js"), b"b").unwrap();
+
+        assert!(wasm_outputs_match(&dir_a, &dir_b).unwrap());
+        assert_eq!(
+            compare_outputs(&dir_a, &dir_b, true).unwrap(),
+            Some(OutputDifference::ContentsDiffer {
+                file_name: "app.js".into()
+            })
+        );
+
+        std::fs::remove_file(dir_b.join("app_bg.wasm")).unwrap();
+        assert!(!wasm_outputs_match(&dir_a, &dir_b).unwrap());
+        assert_eq!(
+            compare_outputs(&dir_a, &dir_b, false).unwrap(),
+            Some(OutputDifference::OnlyIn {
+                dir: dir_a.clone(),
+                file_name: "app_bg.wasm".into()
+            })
+        );
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// A reason wasm-pack can't build a crate, from [`validate_crate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CrateIssue {
+    /// There's no `Cargo.toml` in the crate's directory.
+    ManifestNotFound { path: PathBuf },
+    /// Cargo couldn't read the manifest, like when it isn't valid TOML.
+    /// `message` is what cargo said.
+    CouldntReadManifest { message: String },
+    /// The manifest doesn't have a `[package]`, like a workspace's root
+    /// manifest.
+    NotAPackage { path: PathBuf },
+    /// The crate's `crate-type` doesn't include `cdylib`, so it has no
+    /// `.wasm` to build.
+    NotACdylib { name: String },
+    /// The crate doesn't depend on `wasm-bindgen`, which wasm-pack runs on
+    /// the `.wasm`.
+    MissingWasmBindgen { name: String },
+}
+
+impl Display for CrateIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrateIssue::ManifestNotFound { path } => {
+                write!(f, "Couldn't find a manifest at {:?}", path)
+            }
+            CrateIssue::CouldntReadManifest { message } => {
+                write!(f, "Couldn't read the crate's manifest: {}", message)
+            }
+            CrateIssue::NotAPackage { path } => {
+                write!(f, "The manifest at {:?} isn't a package's", path)
+            }
+            CrateIssue::NotACdylib { name } => write!(
+                f,
+                "The crate `{}` isn't a cdylib. Add `crate-type = [\"cdylib\", \"rlib\"]` to its `[lib]` section",
+                name
+            ),
+            CrateIssue::MissingWasmBindgen { name } => write!(
+                f,
+                "The crate `{}` doesn't depend on wasm-bindgen",
+                name
+            ),
+        }
+    }
+}
+
+/// Check that wasm-pack can build the crate at `path` without building it,
+/// for quick feedback on a misconfigured crate. Returns every issue found.
+///
+/// This reads the manifest with `cargo metadata`, which doesn't need the
+/// network, and checks that the crate is a `cdylib` that depends on
+/// `wasm-bindgen`. A crate without issues can still fail to build, like when
+/// its code doesn't compile.
+pub fn validate_crate(path: impl AsRef<Path>) -> Result<(), Vec<CrateIssue>> {
+    let path = path.as_ref();
+    let manifest_path = path.join("Cargo.toml");
+    // Otherwise cargo would look for a manifest in the parent directories.
+    if !manifest_path.is_file() {
+        return Err(vec![CrateIssue::ManifestNotFound {
+            path: manifest_path,
+        }]);
+    }
+
+    let metadata = crate::workspace::read_cargo_metadata(path).map_err(|error| {
+        vec![CrateIssue::CouldntReadManifest {
+            message: error.to_string(),
+        }]
+    })?;
+
+    // `cargo metadata` lists every package in the workspace, so find ours.
+    let canonical_manifest_path = manifest_path.canonicalize().ok();
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    let package = packages.iter().find(|package| {
+        let package_manifest_path = package["manifest_path"].as_str().map(Path::new);
+        package_manifest_path.and_then(|path| path.canonicalize().ok()) == canonical_manifest_path
+    });
+    let Some(package) = package else {
+        return Err(vec![CrateIssue::NotAPackage {
+            path: manifest_path,
+        }]);
+    };
+
+    let name = package["name"].as_str().unwrap_or_default().to_string();
+    let mut issues = Vec::new();
+
+    let is_cdylib = package["targets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|target| target["crate_types"].as_array().into_iter().flatten())
+        .any(|crate_type| crate_type == "cdylib");
+    if !is_cdylib {
+        issues.push(CrateIssue::NotACdylib { name: name.clone() });
+    }
+
+    // Dev-dependencies aren't available to the library, so they don't count.
+    let depends_on_wasm_bindgen = package["dependencies"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|dependency| dependency["name"] == "wasm-bindgen" && dependency["kind"] != "dev");
+    if !depends_on_wasm_bindgen {
+        issues.push(CrateIssue::MissingWasmBindgen { name });
+    }
+
+    match issues.is_empty() {
+        true => Ok(()),
+        false => Err(issues),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validating_crates() {
+        assert_eq!(validate_crate("test-crate"), Ok(()));
+
+        assert_eq!(
+            validate_crate("."),
+            Err(vec![
+                CrateIssue::NotACdylib {
+                    name: "lib_wasm_pack".to_string()
+                },
+                CrateIssue::MissingWasmBindgen {
+                    name: "lib_wasm_pack".to_string()
+                },
+            ])
+        );
+
+        assert_eq!(
+            validate_crate("target/no-such-crate"),
+            Err(vec![CrateIssue::ManifestNotFound {
+                path: PathBuf::from("target/no-such-crate/Cargo.toml")
+            }])
+        );
+    }
+
+    #[test]
+    fn validating_an_unreadable_manifest() {
+        let dir = Path::new("target/invalid-manifest-test");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package\nname = ").unwrap();
+
+        let issues = validate_crate(dir).unwrap_err();
+        assert!(matches!(
+            issues.as_slice(),
+            [CrateIssue::CouldntReadManifest { .. }]
+        ));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+}
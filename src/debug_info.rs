@@ -0,0 +1,61 @@
+use std::io;
+use std::path::Path;
+
+/// Whether any of the `*_bg.wasm` files in `generated_files` have DWARF debug
+/// info, which is kept in custom sections named `.debug_*`.
+pub(crate) fn any_has_dwarf(generated_files: &[impl AsRef<Path>]) -> io::Result<bool> {
+    for path in generated_files {
+        let path = path.as_ref();
+        if crate::gzip::is_bg_wasm(path) && has_dwarf(&std::fs::read(path)?)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn has_dwarf(wasm: &[u8]) -> io::Result<bool> {
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        let payload = payload.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if let wasmparser::Payload::CustomSection(section) = payload {
+            if section.name().starts_with(".debug_") {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// The reason [`WasmPack::debug_info`](crate::WasmPack::debug_info) is
+/// unsupported.
+pub(crate) fn unsupported_reason(enabled: bool) -> String {
+    format!(
+        "wasm-pack {} has no flag for it. Add `dwarf-debug-info = {}` to `[package.metadata.wasm-pack.profile.<profile>.wasm-bindgen]` in the crate's Cargo.toml instead",
+        crate::WASM_PACK_VERSION,
+        enabled
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A module with nothing but a custom section called `name`.
+    fn module_with_custom_section(name: &str) -> Vec<u8> {
+        let mut section = vec![name.len() as u8];
+        section.extend_from_slice(name.as_bytes());
+
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.push(0);
+        wasm.push(section.len() as u8);
+        wasm.extend(section);
+        wasm
+    }
+
+    #[test]
+    fn finding_dwarf() {
+        assert!(has_dwarf(&module_with_custom_section(".debug_info")).unwrap());
+        assert!(!has_dwarf(&module_with_custom_section("name")).unwrap());
+        assert!(!has_dwarf(b"\0asm\x01\0\0\0").unwrap());
+        assert!(has_dwarf(b"not wasm").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::ffi::OsString;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+use crate::run_id::run_log;
+use crate::{Executable, RunOptions, WasmPackError};
+
+/// A wasm-pack process started by [`WasmPack::spawn_detached`](crate::WasmPack::spawn_detached).
+///
+/// Dropping this doesn't stop wasm-pack. Its output goes to log files rather
+/// than being captured.
+#[derive(Debug)]
+pub struct DetachedBuild {
+    child: Child,
+    stdout_log: PathBuf,
+    stderr_log: PathBuf,
+}
+
+impl DetachedBuild {
+    /// The process ID of wasm-pack.
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// The file wasm-pack's stdout is written to.
+    pub fn stdout_log(&self) -> &Path {
+        &self.stdout_log
+    }
+
+    /// The file wasm-pack's stderr is written to.
+    pub fn stderr_log(&self) -> &Path {
+        &self.stderr_log
+    }
+
+    /// Check whether wasm-pack has finished, without blocking.
+    pub fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Wait for wasm-pack to finish.
+    pub fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        self.child.wait()
+    }
+}
+
+pub(crate) fn spawn_detached(
+    args: Vec<OsString>,
+    options: &RunOptions,
+    log_dir: &Path,
+) -> Result<DetachedBuild, WasmPackError> {
+    run_log!(info, "Spawning detached wasm-pack with args: {:?}", args);
+
+    // A temporary executable could be deleted while wasm-pack is still running,
+    // so the embedded one is always cached.
+    let executable = match &options.executable {
+        Executable::Embedded => {
+            let (path, _) = crate::get_cli_executable_file(&RunOptions {
+                cache_executable: true,
+                ..options.clone()
+            })?;
+            path
+        }
+        Executable::Extracted(path) | Executable::Custom(path) => path.clone(),
+    };
+
+    let stdout_log = log_dir.join("wasm-pack-stdout.log");
+    let stderr_log = log_dir.join("wasm-pack-stderr.log");
+    let create_log = |path: &Path| {
+        std::fs::create_dir_all(log_dir)
+            .and_then(|()| File::create(path))
+            .map_err(WasmPackError::CouldntCreateLogFile)
+    };
+
+    if let Some(on_command) = &options.on_command {
+        on_command.call(&executable, &args);
+    }
+    let mut command = Command::new(executable);
+    command
+        .args(args)
+        .envs(options.env.iter().map(|(name, value)| (name, value)))
+        .stdin(Stdio::null())
+        .stdout(create_log(&stdout_log)?)
+        .stderr(create_log(&stderr_log)?);
+    detach(&mut command);
+    if let Some(arg0) = crate::arg0::for_executable(&options.executable) {
+        crate::arg0::apply_to_command(&mut command, arg0);
+    }
+
+    let child = command
+        .spawn()
+        .map_err(WasmPackError::CouldntInvokeWasmPack)?;
+    run_log!(debug, "Spawned detached wasm-pack with PID {}.", child.id());
+
+    Ok(DetachedBuild {
+        child,
+        stdout_log,
+        stderr_log,
+    })
+}
+
+/// Start wasm-pack in a new session, so it doesn't get signals meant for our
+/// terminal or process group.
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: `setsid` is async-signal-safe, so it's fine to call between
+    // `fork` and `exec`.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Start wasm-pack without a console and in its own process group, so it
+/// doesn't get our console's Ctrl-C.
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_command: &mut Command) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_wasm_pack;
+    use crate::WasmPack;
+
+    #[test]
+    fn detached_builds_get_their_own_session() {
+        let dir = Path::new("target/detached-test");
+        let script = fake_wasm_pack(dir, "#!/bin/sh\necho \"$@\"\necho oops >&2\n");
+
+        let mut build = WasmPack::build("test-crate")
+            .executable(&script)
+            .spawn_detached(dir.join("logs"))
+            .expect("Couldn't spawn detached build.");
+        let session = unsafe { libc::getsid(build.pid() as libc::pid_t) };
+        assert!(build.wait().unwrap().success());
+
+        // The script may have exited before we asked, in which case there's
+        // no session to check.
+        if session != -1 {
+            assert_eq!(session, build.pid() as libc::pid_t);
+        }
+        let stdout = std::fs::read_to_string(build.stdout_log()).unwrap();
+        assert_eq!(stdout, "build test-crate\n");
+        let stderr = std::fs::read_to_string(build.stderr_log()).unwrap();
+        assert_eq!(stderr, "oops\n");
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::{Platform, WasmPackError, WASM_PACK_VERSION};
+
+/// Everything we'd want to know about the environment in a bug report. Get it
+/// with [`diagnostics`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostics {
+    /// The platform whose embedded executable runs here, or why there isn't one.
+    pub platform: Result<Platform, String>,
+    /// The version of the embedded wasm-pack executable.
+    pub bundled_version: String,
+    /// This crate's version.
+    pub crate_version: String,
+    /// `std::env::consts::OS`.
+    pub host_os: String,
+    /// `std::env::consts::ARCH`.
+    pub host_arch: String,
+    /// Whether `rustup` is on the `PATH`.
+    pub rustup_found: bool,
+    /// Whether `cargo` is on the `PATH`.
+    pub cargo_found: bool,
+    /// Whether the directory the executable is extracted to, `target/` in the
+    /// current directory, can be written to.
+    pub temp_dir_writable: bool,
+    /// The most recent error from running wasm-pack in this process.
+    pub last_error: Option<String>,
+}
+
+/// Collect [`Diagnostics`] for a bug report.
+///
+/// ```
+/// let diagnostics = lib_wasm_pack::diagnostics();
+/// println!("{}", serde_json::to_string_pretty(&diagnostics).unwrap());
+/// ```
+pub fn diagnostics() -> Diagnostics {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let found = |name| crate::builder::find_on_path(name, &path).is_some();
+
+    Diagnostics {
+        platform: Platform::current().map_err(|error| error.to_string()),
+        bundled_version: WASM_PACK_VERSION.to_string(),
+        crate_version: crate::CRATE_VERSION.to_string(),
+        host_os: std::env::consts::OS.to_string(),
+        host_arch: std::env::consts::ARCH.to_string(),
+        rustup_found: found("rustup"),
+        cargo_found: found("cargo"),
+        temp_dir_writable: can_write_to(Path::new("target")),
+        last_error: LAST_ERROR
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .clone(),
+    }
+}
+
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+pub(crate) fn record_error(error: &WasmPackError) {
+    *LAST_ERROR.lock().unwrap_or_else(|error| error.into_inner()) = Some(error.to_string());
+}
+
+fn can_write_to(dir: &Path) -> bool {
+    let probe = dir.join(format!("wasm-pack-write-probe-{}", uuid::Uuid::new_v4()));
+    let writable = std::fs::create_dir_all(dir).is_ok() && std::fs::write(&probe, b"").is_ok();
+    let _ignore_errors = std::fs::remove_file(&probe);
+    writable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collecting_diagnostics() {
+        let _ = crate::run(["fake-subcommand"]);
+        let diagnostics = diagnostics();
+
+        assert_eq!(diagnostics.platform, Ok(Platform::current().unwrap()));
+        assert_eq!(diagnostics.bundled_version, WASM_PACK_VERSION);
+        assert!(diagnostics.cargo_found);
+        assert!(diagnostics.temp_dir_writable);
+        assert!(diagnostics.last_error.is_some());
+
+        let json = serde_json::to_value(&diagnostics).unwrap();
+        assert_eq!(json["host_os"], std::env::consts::OS);
+        assert_eq!(
+            json["platform"]["Ok"],
+            Platform::current().unwrap().to_string()
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Downloading wasm-pack when its executable isn't embedded, behind the
+//! `download` feature.
+
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::run_id::run_log;
+use crate::{Platform, WASM_PACK_VERSION};
+
+/// The SHA-256 of each platform's wasm-pack executable, as extracted from its
+/// release archive. These are the same files that are embedded. `None` for
+/// platforms wasm-pack doesn't publish an executable for.
+fn expected_sha256(platform: Platform) -> Option<&'static str> {
+    match platform {
+        Platform::MacOs => Some("ef751cbd049fb9d3a8cba5c2a91d261cb662ea5d6875e0cd87f01af821a17aac"),
+        Platform::LinuxArm => None,
+        Platform::LinuxArm64 => {
+            Some("0ccbe0e625cd4e874925b6ea202df50970ce66f2a760c4436b15f311cbb9d392")
+        }
+        Platform::LinuxX64 => {
+            Some("4317d1aea126870141db635947da4154dced2837aec9dedeb372b7641b4ea717")
+        }
+        Platform::Windows => {
+            Some("f62ee0f234b8e509912f83a5f82c44cf3314977692d208127b78002fcd0b9937")
+        }
+    }
+}
+
+/// Get the wasm-pack executable for `platform` from the download cache in
+/// `target_dir`, downloading it from GitHub first if needed.
+pub(crate) fn cached_or_downloaded(platform: Platform, target_dir: &Path) -> io::Result<Vec<u8>> {
+    let cache_path = target_dir.join(format!(
+        "wasm-pack-download-{}-v{}",
+        platform, WASM_PACK_VERSION
+    ));
+
+    if let Ok(bytes) = std::fs::read(&cache_path) {
+        if verify(platform, &bytes).is_ok() {
+            run_log!(debug, "Reusing downloaded wasm-pack: {:?}", cache_path);
+            return Ok(bytes);
+        }
+        run_log!(
+            debug,
+            "Downloaded wasm-pack is corrupt, downloading it again."
+        );
+    }
+
+    let bytes = download(platform)?;
+    verify(platform, &bytes)?;
+
+    std::fs::create_dir_all(target_dir)?;
+    crate::replace_atomically(&cache_path, |partial_file_path| {
+        std::fs::write(partial_file_path, &bytes)
+    })?;
+    Ok(bytes)
+}
+
+fn download(platform: Platform) -> io::Result<Vec<u8>> {
+    let archive_name = format!("wasm-pack-v{}-{}", WASM_PACK_VERSION, platform);
+    let url = format!(
+        "https://github.com/rustwasm/wasm-pack/releases/download/v{}/{}.tar.gz",
+        WASM_PACK_VERSION, archive_name
+    );
+    run_log!(info, "Downloading wasm-pack from {}", url);
+
+    let response = ureq::get(&url).call().map_err(io::Error::other)?;
+    let mut archive = Vec::new();
+    flate2::read::GzDecoder::new(response.into_body().into_reader()).read_to_end(&mut archive)?;
+
+    let executable_name = match platform {
+        Platform::Windows => "wasm-pack.exe",
+        Platform::MacOs | Platform::LinuxArm | Platform::LinuxArm64 | Platform::LinuxX64 => {
+            "wasm-pack"
+        }
+    };
+    find_in_tar(&archive, &format!("{}/{}", archive_name, executable_name))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| {
+            let message = format!("{} isn't in the release archive", executable_name);
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        })
+}
+
+fn verify(platform: Platform, bytes: &[u8]) -> io::Result<()> {
+    let digest = ring::digest::digest(&ring::digest::SHA256, bytes);
+    let actual: String = digest
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let expected = expected_sha256(platform).ok_or_else(|| {
+        let message = format!("there's no known SHA-256 for wasm-pack on {}", platform);
+        io::Error::new(io::ErrorKind::Unsupported, message)
+    })?;
+    if actual != expected {
+        let message = format!(
+            "downloaded wasm-pack has SHA-256 {}, expected {}",
+            actual, expected
+        );
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    Ok(())
+}
+
+/// Find the file at `path` in an uncompressed tar archive. Release archives are
+/// plain ustar, so that's all this handles.
+fn find_in_tar<'a>(archive: &'a [u8], path: &str) -> Option<&'a [u8]> {
+    const BLOCK: usize = 512;
+    let field = |header: &'a [u8], range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(bytes.len());
+        std::str::from_utf8(&bytes[..end]).ok()
+    };
+
+    let mut offset = 0;
+    while offset + BLOCK <= archive.len() {
+        let header = &archive[offset..offset + BLOCK];
+        let name = field(header, 0..100)?;
+        if name.is_empty() {
+            // Two empty blocks end the archive.
+            return None;
+        }
+
+        let prefix = field(header, 345..500).unwrap_or_default();
+        let size = usize::from_str_radix(field(header, 124..136)?.trim(), 8).ok()?;
+        let data_start = offset + BLOCK;
+        let data = archive.get(data_start..data_start + size)?;
+
+        let full_name = match prefix.is_empty() {
+            true => name.to_string(),
+            false => format!("{}/{}", prefix, name),
+        };
+        if full_name.trim_start_matches("./") == path {
+            return Some(data);
+        }
+
+        offset = data_start + size.div_ceil(BLOCK) * BLOCK;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_entry(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut header = vec![0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        let size = format!("{:011o}", contents.len());
+        header[124..135].copy_from_slice(size.as_bytes());
+        header[156] = b'0';
+
+        let mut entry = header;
+        entry.extend_from_slice(contents);
+        entry.resize(512 + contents.len().div_ceil(512) * 512, 0);
+        entry
+    }
+
+    #[test]
+    fn finding_files_in_tar_archives() {
+        let mut archive = tar_entry("dir/README.md", &[b'a'; 600]);
+        archive.extend(tar_entry("dir/wasm-pack", b"executable"));
+        archive.extend([0; 1024]);
+
+        assert_eq!(
+            find_in_tar(&archive, "dir/wasm-pack"),
+            Some(&b"executable"[..])
+        );
+        assert_eq!(find_in_tar(&archive, "dir/missing"), None);
+    }
+
+    #[test]
+    fn checksums_match_the_embedded_executables() {
+        let platform = Platform::current().unwrap();
+        if let Some(bytes) = crate::bundled_binary(platform) {
+            assert!(verify(platform, bytes).is_ok());
+        }
+        assert!(verify(platform, b"not wasm-pack").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::WasmPackError;
+
+/// What kind of [`WasmPackError`] happened, without the details. Get one with
+/// [`WasmPackError::kind`].
+///
+/// Matching on this is easier than matching on the error itself, whose
+/// variants carry payloads like [`std::io::Error`]s. There's a kind for each
+/// variant, named the same, and new ones may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WasmPackErrorKind {
+    WasmPackReturnedAnError,
+    KilledBySignal,
+    MemoryLimitExceeded,
+    CouldntInvokeWasmPack,
+    CouldntCreateLogFile,
+    Interrupted,
+    CouldntSaveCliExecutableToTemporaryFile,
+    CouldntDeleteTemporaryFile,
+    CouldntExtractCliExecutable,
+    CouldntDownloadWasmPack,
+    CouldntCheckForUpdates,
+    CouldntReadCargoMetadata,
+    WorkspaceMemberNotFound,
+    WorkspaceMemberIsntACdylib,
+    CouldntGzipOutput,
+    CouldntTarOutput,
+    CouldntReadOutDir,
+    CouldntParseWasm,
+    CouldntWriteBuildInfo,
+    CouldntUseToolCacheDir,
+    ManifestPathIsntCargoToml,
+    ManifestNotFound,
+    UnsupportedPlatform,
+    PlatformNotBundled,
+    WasmBindgenNotFound,
+    InvalidConfiguration,
+    UnsupportedOption,
+    WarningsDenied,
+    UnexpectedOutput,
+    BudgetExceeded,
+    TooManyImports,
+}
+
+impl WasmPackError {
+    /// What kind of error this is, to match on without the details.
+    pub fn kind(&self) -> WasmPackErrorKind {
+        match self {
+            WasmPackError::WasmPackReturnedAnError { .. } => {
+                WasmPackErrorKind::WasmPackReturnedAnError
+            }
+            WasmPackError::KilledBySignal { .. } => WasmPackErrorKind::KilledBySignal,
+            WasmPackError::MemoryLimitExceeded { .. } => WasmPackErrorKind::MemoryLimitExceeded,
+            WasmPackError::CouldntInvokeWasmPack(_) => WasmPackErrorKind::CouldntInvokeWasmPack,
+            WasmPackError::CouldntCreateLogFile(_) => WasmPackErrorKind::CouldntCreateLogFile,
+            WasmPackError::Interrupted => WasmPackErrorKind::Interrupted,
+            WasmPackError::CouldntSaveCliExecutableToTemporaryFile(_) => {
+                WasmPackErrorKind::CouldntSaveCliExecutableToTemporaryFile
+            }
+            WasmPackError::CouldntDeleteTemporaryFile(_) => {
+                WasmPackErrorKind::CouldntDeleteTemporaryFile
+            }
+            WasmPackError::CouldntExtractCliExecutable(_) => {
+                WasmPackErrorKind::CouldntExtractCliExecutable
+            }
+            WasmPackError::CouldntDownloadWasmPack(_) => WasmPackErrorKind::CouldntDownloadWasmPack,
+            WasmPackError::CouldntCheckForUpdates(_) => WasmPackErrorKind::CouldntCheckForUpdates,
+            WasmPackError::CouldntReadCargoMetadata(_) => {
+                WasmPackErrorKind::CouldntReadCargoMetadata
+            }
+            WasmPackError::WorkspaceMemberNotFound { .. } => {
+                WasmPackErrorKind::WorkspaceMemberNotFound
+            }
+            WasmPackError::WorkspaceMemberIsntACdylib { .. } => {
+                WasmPackErrorKind::WorkspaceMemberIsntACdylib
+            }
+            WasmPackError::CouldntGzipOutput(_) => WasmPackErrorKind::CouldntGzipOutput,
+            WasmPackError::CouldntTarOutput(_) => WasmPackErrorKind::CouldntTarOutput,
+            WasmPackError::CouldntReadOutDir(_) => WasmPackErrorKind::CouldntReadOutDir,
+            WasmPackError::CouldntParseWasm(_) => WasmPackErrorKind::CouldntParseWasm,
+            WasmPackError::CouldntWriteBuildInfo(_) => WasmPackErrorKind::CouldntWriteBuildInfo,
+            WasmPackError::CouldntUseToolCacheDir(_) => WasmPackErrorKind::CouldntUseToolCacheDir,
+            WasmPackError::ManifestPathIsntCargoToml { .. } => {
+                WasmPackErrorKind::ManifestPathIsntCargoToml
+            }
+            WasmPackError::ManifestNotFound { .. } => WasmPackErrorKind::ManifestNotFound,
+            WasmPackError::UnsupportedPlatform { .. } => WasmPackErrorKind::UnsupportedPlatform,
+            WasmPackError::PlatformNotBundled { .. } => WasmPackErrorKind::PlatformNotBundled,
+            WasmPackError::WasmBindgenNotFound => WasmPackErrorKind::WasmBindgenNotFound,
+            WasmPackError::InvalidConfiguration(_) => WasmPackErrorKind::InvalidConfiguration,
+            WasmPackError::UnsupportedOption { .. } => WasmPackErrorKind::UnsupportedOption,
+            WasmPackError::WarningsDenied { .. } => WasmPackErrorKind::WarningsDenied,
+            WasmPackError::UnexpectedOutput { .. } => WasmPackErrorKind::UnexpectedOutput,
+            WasmPackError::BudgetExceeded { .. } => WasmPackErrorKind::BudgetExceeded,
+            WasmPackError::TooManyImports { .. } => WasmPackErrorKind::TooManyImports,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_match_variants() {
+        let error = WasmPackError::CouldntReadOutDir(std::io::Error::other("gone"));
+        assert_eq!(error.kind(), WasmPackErrorKind::CouldntReadOutDir);
+
+        let error = WasmPackError::WarningsDenied {
+            warnings: vec!["unused variable".to_string()],
+        };
+        assert_eq!(error.kind(), WasmPackErrorKind::WarningsDenied);
+        assert_eq!(
+            WasmPackError::Interrupted.kind(),
+            WasmPackErrorKind::Interrupted
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{Executable, RunOptions, WasmPackError};
+
+/// A [`duct::Expression`] that runs wasm-pack, for slotting it into your own
+/// `duct` pipelines. Get one with [`expression`] or
+/// [`WasmPack::to_expression`](crate::WasmPack::to_expression).
+///
+/// The expression runs an extracted executable, which is deleted when this is
+/// dropped, so keep it around until the expression has finished. Customize the
+/// expression with [`map`](Self::map) to keep the two together.
+///
+/// None of the checks that [`WasmPack::run`](crate::WasmPack::run) does are
+/// done, and the output isn't captured unless you ask `duct` to capture it.
+///
+/// ```no_run
+/// let expression = lib_wasm_pack::expression(["build", "test-crate"])?
+///     .map(|expression| expression.pipe(duct::cmd!("tee", "build.log")));
+/// expression.expression().run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct WasmPackExpression {
+    expression: duct::Expression,
+    executable: PathBuf,
+    is_temporary: bool,
+}
+
+impl WasmPackExpression {
+    /// The expression, to run or to build on.
+    pub fn expression(&self) -> &duct::Expression {
+        &self.expression
+    }
+
+    /// The path of the wasm-pack executable the expression runs.
+    pub fn executable(&self) -> &Path {
+        &self.executable
+    }
+
+    /// Replace the expression with `f(expression)`, like to pipe or redirect
+    /// it, keeping the extracted executable until this is dropped.
+    pub fn map(mut self, f: impl FnOnce(duct::Expression) -> duct::Expression) -> Self {
+        self.expression = f(self.expression.clone());
+        self
+    }
+}
+
+impl Drop for WasmPackExpression {
+    fn drop(&mut self) {
+        if !self.is_temporary {
+            return;
+        }
+        if let Err(error) = std::fs::remove_file(&self.executable) {
+            log::warn!(
+                "Couldn't delete temporary file {:?}: {}",
+                self.executable,
+                error
+            );
+        }
+    }
+}
+
+/// Extract wasm-pack and return an expression that runs it with `args`,
+/// without running it.
+pub fn expression<Args>(args: Args) -> Result<WasmPackExpression, WasmPackError>
+where
+    Args: IntoIterator,
+    Args::Item: Into<OsString>,
+{
+    let args = args.into_iter().map(Into::into).collect();
+    expression_with_options(args, &RunOptions::default())
+}
+
+pub(crate) fn expression_with_options(
+    args: Vec<OsString>,
+    options: &RunOptions,
+) -> Result<WasmPackExpression, WasmPackError> {
+    let (executable, is_temporary) = match &options.executable {
+        Executable::Embedded => {
+            let (path, _) = crate::get_cli_executable_file(options)?;
+            (path, !options.cache_executable)
+        }
+        Executable::Extracted(path) | Executable::Custom(path) => (path.clone(), false),
+    };
+
+    let mut expression = duct::cmd(&executable, args);
+    for (name, value) in &options.env {
+        expression = expression.env(name, value);
+    }
+    expression = match &options.stdin {
+        Some(bytes) => expression.stdin_bytes(bytes.clone()),
+        None => expression.stdin_null(),
+    };
+    if let Some(bytes) = options.memory_limit {
+        expression = crate::memory_limit::apply(expression, bytes);
+    }
+    if let Some(arg0) = crate::arg0::for_executable(&options.executable) {
+        expression = crate::arg0::apply(expression, arg0);
+    }
+
+    Ok(WasmPackExpression {
+        expression,
+        executable,
+        is_temporary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_an_expression() {
+        let expression = expression(["--version"]).expect("Couldn't extract wasm-pack.");
+        let executable = expression.executable().to_path_buf();
+
+        let expression = expression.map(|expression| expression.stdout_capture());
+        let output = expression
+            .expression()
+            .run()
+            .expect("Couldn't run wasm-pack.");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            stdout.trim(),
+            format!("wasm-pack {}", crate::WASM_PACK_VERSION)
+        );
+
+        // The extracted executable only goes away with the expression.
+        assert!(executable.exists());
+        drop(expression);
+        assert!(!executable.exists());
+    }
+}
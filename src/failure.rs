@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+
+/// A known reason for wasm-pack failing, recognized from its output. Get one
+/// with [`WasmPackError::failure`](crate::WasmPackError::failure).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WasmPackFailure {
+    /// The crate depends on a different version of `wasm-bindgen` than the
+    /// `wasm-bindgen` CLI that wasm-pack used.
+    BindgenVersionMismatch {
+        crate_version: String,
+        cli_version: String,
+    },
+    /// Cargo needed to update the crate's `Cargo.lock`, but was passed
+    /// `--locked` or `--frozen`. `lockfile` is its path, if cargo said.
+    LockfileOutOfDate { lockfile: Option<PathBuf> },
+}
+
+impl WasmPackFailure {
+    /// Recognize a known failure from wasm-pack's stderr.
+    pub fn from_stderr(stderr: &str) -> Option<Self> {
+        let crate_version = find_value_after(stderr, "rust wasm file schema version:");
+        let cli_version = find_value_after(stderr, "this binary schema version:");
+        if let (Some(crate_version), Some(cli_version)) = (crate_version, cli_version) {
+            return Some(WasmPackFailure::BindgenVersionMismatch {
+                crate_version,
+                cli_version,
+            });
+        }
+
+        if let Some(lockfile) = find_stale_lockfile(stderr) {
+            return Some(WasmPackFailure::LockfileOutOfDate { lockfile });
+        }
+
+        None
+    }
+}
+
+/// Find cargo's complaint that the lock file is out of date, like "cannot
+/// update the lock file /crate/Cargo.lock because --locked was passed to
+/// prevent this", returning the lock file's path if it's given. Older
+/// versions of cargo say "the lock file ... needs to be updated but --locked
+/// was passed" instead.
+fn find_stale_lockfile(stderr: &str) -> Option<Option<PathBuf>> {
+    stderr.lines().find_map(|line| {
+        let is_stale = ["--locked was passed", "--frozen was passed"]
+            .iter()
+            .any(|flag| line.contains(flag));
+        if !is_stale {
+            return None;
+        }
+        let (_, rest) = line.split_once("the lock file")?;
+
+        let path = [" because ", " needs to be updated"]
+            .iter()
+            .find_map(|end| rest.split_once(end))
+            .map(|(path, _)| path.trim())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        Some(path)
+    })
+}
+
+/// Find the first line containing `label` (ignoring case), and return the
+/// rest of the line after it.
+fn find_value_after(text: &str, label: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let start = line.to_lowercase().find(label)? + label.len();
+        let value = line.get(start..)?.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+impl std::fmt::Display for WasmPackFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WasmPackFailure::BindgenVersionMismatch {
+                crate_version,
+                cli_version,
+            } => {
+                write!(
+                    f,
+                    "The crate uses wasm-bindgen {}, but the wasm-bindgen CLI is version {}. ",
+                    crate_version, cli_version
+                )?;
+                write!(
+                    f,
+                    "Either run `cargo update -p wasm-bindgen --precise {}` in the crate, ",
+                    cli_version
+                )?;
+                write!(
+                    f,
+                    "or install the matching CLI with `cargo install -f wasm-bindgen-cli --version {}`.",
+                    crate_version
+                )
+            }
+            WasmPackFailure::LockfileOutOfDate { lockfile } => {
+                match lockfile {
+                    Some(lockfile) => write!(f, "{} is out of date", lockfile.display())?,
+                    None => write!(f, "The crate's Cargo.lock is out of date")?,
+                }
+                write!(
+                    f,
+                    ", but cargo was passed `--locked` or `--frozen`, so it can't update it. "
+                )?;
+                write!(
+                    f,
+                    "Either update it with `cargo update --workspace` and commit it, or build without `--locked`."
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizing_a_bindgen_version_mismatch() {
+        let stderr = r#"[INFO]: Installing wasm-bindgen...
+Error: 
+
+it looks like the Rust project used to create this wasm file was linked against
+version of wasm-bindgen that uses a different bindgen format than this binary:
+
+  rust wasm file schema version: 0.2.87
+     this binary schema version: 0.2.92
+
+Currently the bindgen format is unstable enough that these two schema versions
+must exactly match. You can accomplish this by either updating this binary or
+the wasm-bindgen dependency in the Rust project."#;
+
+        assert_eq!(
+            WasmPackFailure::from_stderr(stderr),
+            Some(WasmPackFailure::BindgenVersionMismatch {
+                crate_version: "0.2.87".to_string(),
+                cli_version: "0.2.92".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn recognizing_an_out_of_date_lockfile() {
+        let stderr = "error: cannot update the lock file /crate/Cargo.lock because --locked was passed to prevent this";
+        assert_eq!(
+            WasmPackFailure::from_stderr(stderr),
+            Some(WasmPackFailure::LockfileOutOfDate {
+                lockfile: Some(PathBuf::from("/crate/Cargo.lock")),
+            })
+        );
+
+        let stderr =
+            "error: the lock file needs to be updated but --frozen was passed to prevent this";
+        assert_eq!(
+            WasmPackFailure::from_stderr(stderr),
+            Some(WasmPackFailure::LockfileOutOfDate { lockfile: None })
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn building_with_an_out_of_date_lockfile() {
+        use crate::test_support::fake_wasm_pack;
+        use std::path::Path;
+
+        // The lock file is missing the path dependency, so cargo would need
+        // to add it.
+        let dir = Path::new("target/stale-lockfile-test");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        for (path, contents) in [
+            (
+                "Cargo.toml",
+                "[package]\nname = \"stale-lockfile\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nhelper = { path = \"helper\" }\n",
+            ),
+            (
+                "Cargo.lock",
+                "version = 3\n\n[[package]]\nname = \"stale-lockfile\"\nversion = \"0.1.0\"\n",
+            ),
+            ("src/lib.rs", ""),
+            (
+                "helper/Cargo.toml",
+                "[package]\nname = \"helper\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("helper/src/lib.rs", ""),
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        // wasm-pack updates the lock file itself with `cargo metadata` before
+        // building, unless it can't write to it, so run only cargo's part.
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let contents = format!(
+            "#!/bin/sh\n{} build --locked --manifest-path {}\n",
+            crate::shell_script::quote(&cargo),
+            crate::shell_script::quote(&dir.join("Cargo.toml").to_string_lossy())
+        );
+        let script = fake_wasm_pack(dir, &contents);
+
+        let error = crate::WasmPack::login()
+            .executable(&script)
+            .run()
+            .expect_err("The build should have failed.");
+        assert!(matches!(
+            error.failure(),
+            Some(WasmPackFailure::LockfileOutOfDate { lockfile: Some(lockfile) })
+                if lockfile.ends_with("stale-lockfile-test/Cargo.lock")
+        ));
+        assert!(error.to_string().contains("build without `--locked`"));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unrecognized_failures() {
+        let stderr = "Error: crate directory is missing a `Cargo.toml` file";
+        assert_eq!(WasmPackFailure::from_stderr(stderr), None);
+    }
+}
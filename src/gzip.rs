@@ -0,0 +1,130 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A `*_bg.wasm` file that was compressed after a build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GzippedWasm {
+    path: PathBuf,
+    original_size: u64,
+    compressed_size: u64,
+}
+
+impl GzippedWasm {
+    /// The path of the `.wasm.gz` file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The size of the `.wasm` file in bytes.
+    pub fn original_size(&self) -> u64 {
+        self.original_size
+    }
+
+    /// The size of the `.wasm.gz` file in bytes.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+}
+
+/// Whether `path` is a `*_bg.wasm` file, which is what wasm-pack calls the
+/// `.wasm` files it generates.
+pub(crate) fn is_bg_wasm(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with("_bg.wasm"))
+}
+
+/// Fail with an [`io::ErrorKind::InvalidInput`] error unless `level` is from
+/// 0 (no compression) to 9 (best compression).
+pub(crate) fn check_level(level: u32) -> io::Result<()> {
+    if level > 9 {
+        let reason = format!(
+            "The gzip level {} is out of range, it must be 0 to 9",
+            level
+        );
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, reason));
+    }
+    Ok(())
+}
+
+/// Gzip `bytes` in memory at `level`.
+pub(crate) fn compress(bytes: &[u8], level: u32) -> io::Result<Vec<u8>> {
+    check_level(level)?;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+/// Compress each `*_bg.wasm` file in `generated_files` to a `*_bg.wasm.gz`
+/// file next to it. `level` ranges from 0 (no compression) to 9 (best
+/// compression).
+pub(crate) fn gzip_wasm_files(
+    generated_files: &[impl AsRef<Path>],
+    level: u32,
+) -> io::Result<Vec<GzippedWasm>> {
+    let mut gzipped = Vec::new();
+
+    for path in generated_files {
+        let path = path.as_ref();
+        if !is_bg_wasm(path) {
+            continue;
+        }
+
+        let bytes = std::fs::read(path)?;
+        let compressed = compress(&bytes, level)?;
+
+        let mut gzipped_path = path.as_os_str().to_owned();
+        gzipped_path.push(".gz");
+        let gzipped_path = PathBuf::from(gzipped_path);
+        std::fs::write(&gzipped_path, &compressed)?;
+
+        gzipped.push(GzippedWasm {
+            path: gzipped_path,
+            original_size: bytes.len() as u64,
+            compressed_size: compressed.len() as u64,
+        });
+    }
+
+    Ok(gzipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn gzipping_wasm_files() {
+        let out_dir = Path::new("target/gzip-test");
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+        std::fs::create_dir_all(out_dir).unwrap();
+
+        let wasm = vec![0u8; 4096];
+        std::fs::write(out_dir.join("crate_bg.wasm"), &wasm).unwrap();
+        std::fs::write(out_dir.join("crate.js"), "export {};").unwrap();
+
+        let generated_files = [out_dir.join("crate_bg.wasm"), out_dir.join("crate.js")];
+        let gzipped = gzip_wasm_files(&generated_files, 9).expect("Couldn't gzip wasm files.");
+        assert_eq!(gzipped.len(), 1);
+        assert_eq!(gzipped[0].path(), out_dir.join("crate_bg.wasm.gz"));
+        assert_eq!(gzipped[0].original_size(), 4096);
+        assert!(gzipped[0].compressed_size() < 4096);
+
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(gzipped[0].path()).unwrap())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, wasm);
+        assert!(!out_dir.join("crate.js.gz").exists());
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+    }
+
+    #[test]
+    fn levels_above_9_are_rejected() {
+        assert!(compress(b"wasm", 9).is_ok());
+        let error = compress(b"wasm", 10).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("0 to 9"));
+    }
+}
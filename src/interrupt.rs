@@ -0,0 +1,94 @@
+//! Stopping wasm-pack when we're interrupted.
+//!
+//! wasm-pack's output is captured, so if Ctrl-C only reaches this process,
+//! wasm-pack would keep running in the background. While a run is in progress
+//! we count SIGINTs, and [`DuctRunner`](crate::DuctRunner) kills wasm-pack
+//! when the count changes. The previous handler is restored when the last run
+//! finishes.
+
+#[cfg(unix)]
+mod imp {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Handler {
+        active_runs: usize,
+        previous: Option<libc::sigaction>,
+    }
+
+    static HANDLER: Mutex<Handler> = Mutex::new(Handler {
+        active_runs: 0,
+        previous: None,
+    });
+
+    extern "C" fn count_interrupt(_signal: libc::c_int) {
+        // Atomics are async-signal-safe, unlike most things.
+        INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn interrupts_received() -> usize {
+        INTERRUPTS.load(Ordering::SeqCst)
+    }
+
+    pub(crate) struct InterruptGuard(());
+
+    impl InterruptGuard {
+        pub(crate) fn install() -> Self {
+            let mut handler = HANDLER.lock().unwrap_or_else(|error| error.into_inner());
+            if handler.active_runs == 0 {
+                // SAFETY: `count_interrupt` only touches an atomic, and both
+                // structs are fully initialized before they're used.
+                unsafe {
+                    let mut action: libc::sigaction = std::mem::zeroed();
+                    action.sa_sigaction = count_interrupt as extern "C" fn(libc::c_int) as usize;
+                    action.sa_flags = libc::SA_RESTART;
+                    libc::sigemptyset(&mut action.sa_mask);
+
+                    let mut previous: libc::sigaction = std::mem::zeroed();
+                    if libc::sigaction(libc::SIGINT, &action, &mut previous) == 0 {
+                        handler.previous = Some(previous);
+                    }
+                }
+            }
+            handler.active_runs += 1;
+            InterruptGuard(())
+        }
+    }
+
+    impl Drop for InterruptGuard {
+        fn drop(&mut self) {
+            let mut handler = HANDLER.lock().unwrap_or_else(|error| error.into_inner());
+            handler.active_runs -= 1;
+            if handler.active_runs == 0 {
+                if let Some(previous) = handler.previous.take() {
+                    // SAFETY: `previous` is what `sigaction` gave us.
+                    unsafe {
+                        libc::sigaction(libc::SIGINT, &previous, std::ptr::null_mut());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    // Ctrl-C reaches every process attached to the console on Windows, so
+    // wasm-pack stops by itself.
+
+    pub(crate) fn interrupts_received() -> usize {
+        0
+    }
+
+    pub(crate) struct InterruptGuard(());
+
+    impl InterruptGuard {
+        pub(crate) fn install() -> Self {
+            InterruptGuard(())
+        }
+    }
+}
+
+pub(crate) use imp::{interrupts_received, InterruptGuard};
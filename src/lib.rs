@@ -1,10 +1,148 @@
 use std::fmt::Display;
 use std::io;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+mod arg0;
+
+mod builder;
+pub use builder::WasmPack;
+
+pub mod subcommand;
+
+mod outputs;
+pub use outputs::{BuildOutput, PackOutput, TestOutput, VersionOutput};
+
+mod run_id;
+use run_id::run_log;
+
+mod shell_script;
+
+mod size_report;
+
+pub mod sink;
+pub use sink::OutputSink;
+
+mod snapshot;
+
+mod tarball;
+pub use tarball::{build_to_tar, build_to_tar_gz};
+
+mod session;
+pub use session::{
+    build_profiles, build_targets, FinishedRun, RunAllError, RunSummary, WasmPackSession,
+};
+
+mod run_async;
+pub use run_async::{run_async_with_callback, WasmPackFuture};
+
+mod runner;
+pub use runner::{CommandRunner, DuctRunner, Invocation};
+
+mod profile;
+pub use profile::BuildProfile;
+
+mod target;
+pub use target::{expected_entry_js, Target};
+
+#[cfg(test)]
+mod test_support;
+
+mod version;
+pub use version::{parse_version_output, Version};
+
+mod wasm_opt;
+pub use wasm_opt::OptLevel;
+
+mod browser;
+pub use browser::Browser;
+
+mod build_info;
+
+mod capture;
+
+mod cargo_message;
+pub use cargo_message::{CargoMessage, CargoMessageSpan};
+
+mod cleanup;
+pub use cleanup::{cleanup_stale_binaries, clear_cache};
+
+mod compare;
+pub use compare::{compare_outputs, wasm_outputs_match, OutputDifference};
+
+mod crate_issues;
+pub use crate_issues::{validate_crate, CrateIssue};
+
+mod debug_info;
+
+mod detached;
+pub use detached::DetachedBuild;
+
+mod diagnostics;
+pub use diagnostics::{diagnostics, Diagnostics};
+
+mod interrupt;
+
+mod lines;
+pub use lines::{OutputLine, OutputStream, RecentLines};
+
+mod memory_limit;
+
+mod output_name;
+
+mod out_dir_guard;
+
+mod paths;
+
+mod progress;
+
+mod platform;
+pub use platform::{bundled_binary, Platform, UnknownPlatform};
+
+#[cfg(feature = "download")]
+mod download;
+
+mod error_kind;
+pub use error_kind::WasmPackErrorKind;
+
+mod expression;
+pub use expression::{expression, WasmPackExpression};
+
+mod failure;
+pub use failure::WasmPackFailure;
+
+mod gzip;
+pub use gzip::GzippedWasm;
+
+#[cfg(feature = "update-check")]
+mod update_check;
+#[cfg(feature = "update-check")]
+pub use update_check::check_for_updates;
+
+mod wasm_stats;
+pub use wasm_stats::WasmStats;
+
+mod workspace;
 
 const CRATE_VERSION: &str = include_cargo_toml::include_toml!("package"."version");
 
+/// The version of the embedded wasm-pack executables.
+pub const WASM_PACK_VERSION: &str = "0.12.1";
+
+/// The environment variable that makes every run extract a fresh wasm-pack
+/// executable when it's `1` or `true`, even with
+/// [`WasmPack::cache_executable`]. A way to rule out a bad cache without
+/// changing any code.
+///
+/// With the `download` feature, the downloaded executable is still reused,
+/// since it's checked against its SHA-256 every time anyway.
+pub const FORCE_EXTRACT_ENV_VAR: &str = "WASM_PACK_FORCE_EXTRACT";
+
+/// How many lines of wasm-pack's stdout and stderr a
+/// [`WasmPackError::WasmPackReturnedAnError`] displays, unless set with
+/// [`WasmPack::error_tail_lines`].
+pub const DEFAULT_ERROR_TAIL_LINES: usize = 50;
+
 /// Run wasm-pack with the given arguments.
 ///
 /// ```
@@ -21,54 +159,539 @@ const CRATE_VERSION: &str = include_cargo_toml::include_toml!("package"."version
 ///
 /// lib_wasm_pack::run(args).expect("Running wasm-pack failed.");
 /// ```
+///
+/// The arguments are passed on as they are, so they have to be in the order
+/// wasm-pack expects. In particular, cargo flags like `--features`,
+/// `--all-features` and `--no-default-features` have to go after the input
+/// crate path and a `--` separator, like `["build", "test-crate", "--",
+/// "--features", "serde"]`, or they aren't passed to cargo. The
+/// [`WasmPack`] builder's [`features`](WasmPack::features) puts them there.
 pub fn run<Args>(args: Args) -> Result<WasmPackOutput, WasmPackError>
 where
     Args: IntoIterator,
     Args::Item: Into<std::ffi::OsString>,
 {
     let args = args.into_iter().map(Into::into).collect::<Vec<_>>();
-    println!("Running wasm-pack with args: {:?}", args);
+    run_with_options(args, &RunOptions::default())
+}
 
-    let path_to_cli_executable = get_cli_executable_file()?;
-    println!("Got CLI executable file: {:?}", path_to_cli_executable);
-    println!("Executing CLI executable...");
-    let output = duct::cmd(&path_to_cli_executable, args)
-        .stderr_capture()
-        .stdout_capture()
-        .unchecked()
-        .run()
-        .map_err(WasmPackError::CouldntInvokeWasmPack)?;
+/// Time a single extraction of the embedded wasm-pack executable, followed by
+/// deleting it, without running wasm-pack.
+///
+/// Useful for deciding whether [`WasmPack::cache_executable`] is worth it on a
+/// given machine.
+pub fn benchmark_extraction() -> Result<std::time::Duration, WasmPackError> {
+    let (duration, _path) = time_extraction()?;
+    Ok(duration)
+}
 
-    let (stdout, stderr) = get_stdout_and_stderr_from_process_output(&output);
+fn time_extraction() -> Result<(std::time::Duration, PathBuf), WasmPackError> {
+    let start = std::time::Instant::now();
+    let (path_to_cli_executable, _) = get_cli_executable_file(&RunOptions::default())?;
+    std::fs::remove_file(&path_to_cli_executable)
+        .map_err(WasmPackError::CouldntDeleteTemporaryFile)?;
+    Ok((start.elapsed(), path_to_cli_executable))
+}
 
-    println!("CLI executable finished executing.");
-    println!("CLI executable stdout: {}", &stdout);
-    println!("CLI executable stderr: {}", &stderr);
+/// Where running wasm-pack extracts the executable for `platform`, like to
+/// check ahead of time that the directory can be written to and has room for
+/// it. Nothing is created.
+///
+/// Extracted executables are named after a random UUID that's picked again
+/// for every run, so only the directory and the shape of the name can be
+/// relied on. [`WasmPack::planned_temp_path`] gives the exact path when a
+/// builder sets [`temp_name_suffix`](WasmPack::temp_name_suffix) or
+/// [`cache_executable`](WasmPack::cache_executable).
+pub fn planned_temp_path(platform: Platform) -> PathBuf {
+    planned_extraction_path(platform, &RunOptions::default())
+}
 
-    std::fs::remove_file(path_to_cli_executable)
-        .map_err(WasmPackError::CouldntDeleteTemporaryFile)?;
-    println!("Deleted temporary file.");
+pub(crate) fn planned_extraction_path(platform: Platform, options: &RunOptions) -> PathBuf {
+    // Extraction fails later if there's no current directory; until then,
+    // `target` is relative to wherever that is.
+    let target_dir = std::env::current_dir().unwrap_or_default().join("target");
 
-    if !output.status.success() {
-        println!("CLI executable returned an error.");
-        let error = WasmPackError::WasmPackReturnedAnError { stdout, stderr };
+    let path = if options.cache_executable {
+        target_dir.join(cached_file_name(platform))
+    } else {
+        target_dir.join(temp_file_name(platform, options))
+    };
+    paths::extended_length_path(&path).unwrap_or(path)
+}
+
+/// Write the wasm-pack executable for `platform` to `path`, and make it
+/// executable on Unix.
+///
+/// Unlike the executables extracted when running, this one isn't deleted
+/// afterwards. If there's already a file at `path`, it's overwritten.
+pub fn extract_to(path: &Path, platform: Platform) -> Result<(), WasmPackError> {
+    let target_dir = Path::new("target");
+    let cli_executable_bytes = platform::get_cli_executable_bytes(platform, target_dir)?;
+    write_cli_executable(path, &cli_executable_bytes, DEFAULT_EXECUTABLE_MODE, true)
+        .map_err(WasmPackError::CouldntExtractCliExecutable)
+}
+
+/// Which wasm-pack executable to run.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Executable {
+    /// Extract the embedded executable for this run.
+    #[default]
+    Embedded,
+    /// Run an embedded executable that was already extracted. It's left in
+    /// place afterwards.
+    Extracted(PathBuf),
+    /// Run an executable supplied by the caller.
+    Custom(PathBuf),
+}
+
+/// Options that control how wasm-pack is run, as opposed to the arguments
+/// passed to it. Set through the [`WasmPack`] builder.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RunOptions {
+    pub(crate) cache_executable: bool,
+    pub(crate) force_extract: bool,
+    pub(crate) runner: Option<std::sync::Arc<dyn CommandRunner>>,
+    pub(crate) executable: Executable,
+    pub(crate) env: Vec<(std::ffi::OsString, std::ffi::OsString)>,
+    pub(crate) ignore_interrupts: bool,
+    pub(crate) max_output_bytes: Option<usize>,
+    pub(crate) strict_permissions: bool,
+    pub(crate) executable_mode: Option<u32>,
+    pub(crate) on_line: Option<lines::LineCallback>,
+    pub(crate) error_tail_lines: Option<usize>,
+    pub(crate) stdin: Option<Vec<u8>>,
+    pub(crate) log_output: Option<log::Level>,
+    pub(crate) temp_name_suffix: Option<NameSuffix>,
+    pub(crate) cancelled: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    pub(crate) memory_limit: Option<u64>,
+    pub(crate) on_command: Option<CommandCallback>,
+    pub(crate) stdout_sink: Option<std::sync::Arc<dyn OutputSink>>,
+    pub(crate) stderr_sink: Option<std::sync::Arc<dyn OutputSink>>,
+    pub(crate) on_progress: Option<progress::ProgressCallback>,
+}
+
+/// Makes the names of extracted executables unique.
+#[derive(Clone)]
+pub(crate) struct NameSuffix(pub(crate) std::sync::Arc<dyn Fn() -> String + Send + Sync>);
+
+impl std::fmt::Debug for NameSuffix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("NameSuffix")
+    }
+}
+
+type CommandFn = dyn Fn(&Path, &[std::ffi::OsString]) + Send + Sync;
+
+/// Called with the executable and the arguments, with secrets redacted, just
+/// before wasm-pack is started.
+#[derive(Clone)]
+pub(crate) struct CommandCallback(pub(crate) std::sync::Arc<CommandFn>);
+
+impl CommandCallback {
+    pub(crate) fn call(&self, executable: &Path, args: &[std::ffi::OsString]) {
+        let args = build_info::redact(args)
+            .into_iter()
+            .map(std::ffi::OsString::from)
+            .collect::<Vec<_>>();
+        (self.0)(executable, &args);
+    }
+}
+
+impl std::fmt::Debug for CommandCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CommandCallback")
+    }
+}
+
+/// The callback for each line of output, if anything wants them.
+fn line_callback(options: &RunOptions) -> Option<lines::LineCallback> {
+    let callback = match options.log_output {
+        Some(level) => {
+            // Lines are read on other threads, which don't know the current run.
+            let run_id = run_id::current().map(run_id::short).unwrap_or_default();
+            Some(lines::logging_callback(
+                level,
+                run_id,
+                options.on_line.clone(),
+            ))
+        }
+        None => options.on_line.clone(),
+    };
+
+    // This goes first, so the other callbacks don't see cargo's progress bar.
+    match &options.on_progress {
+        Some(on_progress) => Some(progress::progress_callback(on_progress.clone(), callback)),
+        None => callback,
+    }
+}
+
+pub(crate) fn run_with_options(
+    args: Vec<std::ffi::OsString>,
+    options: &RunOptions,
+) -> Result<WasmPackOutput, WasmPackError> {
+    let run_id = uuid::Uuid::new_v4();
+    let _run_id_guard = run_id::RunIdGuard::enter(run_id);
+    let result = run_and_check(args, options);
+    if let Err(error) = &result {
+        diagnostics::record_error(error);
+    }
+    result
+}
+
+/// Run wasm-pack with the given arguments, like [`run`], but return its output
+/// and exit status whether or not it succeeded, for callers that decide what
+/// a failure means themselves, like a test runner expecting one.
+///
+/// Only problems with running wasm-pack at all, like not being able to
+/// extract it, are errors.
+pub fn run_raw<Args>(args: Args) -> Result<RawOutput, WasmPackError>
+where
+    Args: IntoIterator,
+    Args::Item: Into<std::ffi::OsString>,
+{
+    let args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+    let run_id = uuid::Uuid::new_v4();
+    let _run_id_guard = run_id::RunIdGuard::enter(run_id);
+    let result = execute(args, &RunOptions::default());
+    if let Err(error) = &result {
+        diagnostics::record_error(error);
+    }
+
+    let execution = result?;
+    Ok(RawOutput {
+        stdout: execution.captured.stdout,
+        stderr: execution.captured.stderr,
+        status: execution.status,
+        was_truncated: execution.captured.was_truncated,
+        run_id: run_id::current().map(run_id::short).unwrap_or_default(),
+        duration: execution.start.elapsed(),
+    })
+}
+
+/// A finished run of the wasm-pack executable, successful or not.
+struct Execution {
+    status: std::process::ExitStatus,
+    captured: CapturedOutput,
+    start: std::time::Instant,
+    extraction_duration: std::time::Duration,
+    execution_duration: std::time::Duration,
+    executable_permissions: Option<u32>,
+    extracted_from_cache: bool,
+}
+
+/// Get the executable, run it, and clean up after it, without judging how it
+/// went.
+fn execute(
+    args: Vec<std::ffi::OsString>,
+    options: &RunOptions,
+) -> Result<Execution, WasmPackError> {
+    run_log!(info, "Running wasm-pack with args: {:?}", args);
+    let start = std::time::Instant::now();
+
+    let (path_to_cli_executable, extracted_from_cache) = match &options.executable {
+        Executable::Embedded => get_cli_executable_file(options)?,
+        Executable::Extracted(path) | Executable::Custom(path) => (path.clone(), false),
+    };
+    let extraction_duration = start.elapsed();
+    run_log!(
+        debug,
+        "Got CLI executable file: {:?} (from cache: {})",
+        path_to_cli_executable,
+        extracted_from_cache
+    );
+    let executable_permissions = match &options.executable {
+        Executable::Embedded | Executable::Extracted(_) => permission_bits(&path_to_cli_executable),
+        Executable::Custom(_) => None,
+    };
+    if let Some(bits) = executable_permissions {
+        run_log!(debug, "CLI executable permissions: {:o}", bits);
+    }
+    run_log!(debug, "Executing CLI executable...");
+    let invocation = options.env.iter().fold(
+        Invocation::new(path_to_cli_executable.clone(), args)
+            .max_output_bytes(options.max_output_bytes)
+            .on_line(line_callback(options))
+            .stdin(options.stdin.clone())
+            .cancelled(options.cancelled.clone())
+            .memory_limit(options.memory_limit)
+            .arg0(arg0::for_executable(&options.executable))
+            .sinks(options.stdout_sink.clone(), options.stderr_sink.clone()),
+        |invocation, (name, value)| invocation.env(name, value),
+    );
+    if let Some(on_command) = &options.on_command {
+        on_command.call(invocation.executable(), invocation.args());
+    }
+    let execution_start = std::time::Instant::now();
+    let interrupt_guard = (!options.ignore_interrupts).then(interrupt::InterruptGuard::install);
+    let result = match &options.runner {
+        Some(runner) => runner.run(&invocation),
+        None => DuctRunner.run(&invocation),
+    };
+    drop(interrupt_guard);
+    let execution_duration = execution_start.elapsed();
+
+    // Clean up before checking for errors, so an interrupted run doesn't leave
+    // the executable behind.
+    let is_temporary = matches!(options.executable, Executable::Embedded);
+    if is_temporary && !options.cache_executable {
+        std::fs::remove_file(path_to_cli_executable)
+            .map_err(WasmPackError::CouldntDeleteTemporaryFile)?;
+        run_log!(debug, "Deleted temporary file.");
+    }
+
+    let output = result.map_err(|error| match error.kind() {
+        io::ErrorKind::Interrupted => WasmPackError::Interrupted,
+        _ => WasmPackError::CouldntInvokeWasmPack(error),
+    })?;
+
+    let status = output.status;
+    let mut captured = get_stdout_and_stderr_from_process_output(output, options.max_output_bytes);
+    if options.on_progress.is_some() {
+        captured.stderr = progress::strip_progress_bars(&captured.stderr);
+    }
+
+    run_log!(debug, "CLI executable finished executing.");
+    run_log!(debug, "CLI executable stdout: {}", &captured.stdout);
+    run_log!(debug, "CLI executable stderr: {}", &captured.stderr);
+
+    Ok(Execution {
+        status,
+        captured,
+        start,
+        extraction_duration,
+        execution_duration,
+        executable_permissions,
+        extracted_from_cache,
+    })
+}
+
+fn run_and_check(
+    args: Vec<std::ffi::OsString>,
+    options: &RunOptions,
+) -> Result<WasmPackOutput, WasmPackError> {
+    let profile = BuildProfile::from_args(&args);
+    let Execution {
+        status,
+        captured,
+        start,
+        extraction_duration,
+        execution_duration,
+        executable_permissions,
+        extracted_from_cache,
+    } = execute(args, options)?;
+    let success = status.success();
+    let signal = exit_signal(&status);
+
+    let tail_lines = options.error_tail_lines.unwrap_or(DEFAULT_ERROR_TAIL_LINES);
+    if let Some(limit) = options.memory_limit {
+        if (!success || signal.is_some()) && memory_limit::was_exceeded(&captured.stderr, signal) {
+            run_log!(info, "CLI executable ran out of memory.");
+            return Err(WasmPackError::MemoryLimitExceeded {
+                limit,
+                stdout: captured.stdout,
+                stderr: captured.stderr,
+                tail_lines,
+            });
+        }
+    }
+
+    if let Some(signal) = signal {
+        run_log!(info, "CLI executable was killed by signal {}.", signal);
+        return Err(WasmPackError::KilledBySignal {
+            signal,
+            stdout: captured.stdout,
+            stderr: captured.stderr,
+            tail_lines,
+        });
+    }
+
+    if !success {
+        run_log!(info, "CLI executable returned an error.");
+        let error = WasmPackError::WasmPackReturnedAnError {
+            stdout: captured.stdout,
+            stderr: captured.stderr,
+            tail_lines,
+        };
         return Err(error);
     }
 
-    println!("CLI executable returned successfully.");
-    let output = WasmPackOutput::new(stdout, stderr);
+    run_log!(info, "CLI executable returned successfully.");
+    let mut output = WasmPackOutput::new(captured, profile);
+    output.duration = start.elapsed();
+    output.extraction_duration = extraction_duration;
+    output.execution_duration = execution_duration;
+    output.run_id = run_id::current().map(run_id::short).unwrap_or_default();
+    match &options.executable {
+        Executable::Custom(path) => output.custom_executable = Some(path.clone()),
+        Executable::Embedded | Executable::Extracted(_) => {
+            output.platform = Platform::current().ok();
+            output.executable_permissions = executable_permissions;
+            output.extracted_from_cache = extracted_from_cache;
+        }
+    }
     Ok(output)
 }
 
+/// What [`run_raw`] returns: wasm-pack's output and exit status, whether or
+/// not it succeeded.
+#[derive(Debug, Clone)]
+pub struct RawOutput {
+    stdout: String,
+    stderr: String,
+    status: std::process::ExitStatus,
+    was_truncated: bool,
+    run_id: String,
+    duration: std::time::Duration,
+}
+
+impl RawOutput {
+    pub fn stdout(&self) -> &str {
+        &self.stdout
+    }
+
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+
+    pub fn status(&self) -> std::process::ExitStatus {
+        self.status
+    }
+
+    /// Whether wasm-pack exited with a zero exit code.
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// wasm-pack's exit code. `None` if it was killed by a signal.
+    pub fn code(&self) -> Option<i32> {
+        self.status.code()
+    }
+
+    /// The signal that killed wasm-pack, if one did. Always `None` outside
+    /// Unix.
+    pub fn signal(&self) -> Option<i32> {
+        exit_signal(&self.status)
+    }
+
+    /// See [`WasmPackOutput::was_truncated`].
+    pub fn was_truncated(&self) -> bool {
+        self.was_truncated
+    }
+
+    /// See [`WasmPackOutput::run_id`].
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// How long the run took, including extracting the executable.
+    pub fn duration(&self) -> std::time::Duration {
+        self.duration
+    }
+}
+
 #[derive(Debug)]
 pub struct WasmPackOutput {
     stdout: String,
     stderr: String,
+    stdout_was_lossy: bool,
+    stderr_was_lossy: bool,
+    was_truncated: bool,
+    run_id: String,
+    gzipped_wasm: Vec<GzippedWasm>,
+    profile: Option<BuildProfile>,
+    custom_executable: Option<PathBuf>,
+    executed_version: std::sync::OnceLock<String>,
+    platform: Option<Platform>,
+    duration: std::time::Duration,
+    extraction_duration: std::time::Duration,
+    execution_duration: std::time::Duration,
+    executable_permissions: Option<u32>,
+    extracted_from_cache: bool,
+    out_dir: Option<PathBuf>,
+    generated_files: Vec<PathBuf>,
+    wasm_size: u64,
+    output_name: Option<String>,
+    out_dir_guard: Option<out_dir_guard::OutDirGuard>,
 }
 
 impl WasmPackOutput {
-    fn new(stdout: String, stderr: String) -> Self {
-        Self { stdout, stderr }
+    fn new(captured: CapturedOutput, profile: Option<BuildProfile>) -> Self {
+        Self {
+            stdout: captured.stdout,
+            stderr: captured.stderr,
+            stdout_was_lossy: captured.stdout_was_lossy,
+            stderr_was_lossy: captured.stderr_was_lossy,
+            was_truncated: captured.was_truncated,
+            run_id: String::new(),
+            gzipped_wasm: Vec::new(),
+            profile,
+            custom_executable: None,
+            executed_version: std::sync::OnceLock::new(),
+            platform: None,
+            duration: std::time::Duration::ZERO,
+            extraction_duration: std::time::Duration::ZERO,
+            execution_duration: std::time::Duration::ZERO,
+            executable_permissions: None,
+            extracted_from_cache: false,
+            out_dir: None,
+            generated_files: Vec::new(),
+            wasm_size: 0,
+            output_name: None,
+            out_dir_guard: None,
+        }
+    }
+
+    /// Record where a build put its output, and which files are there.
+    pub(crate) fn set_out_dir(
+        &mut self,
+        out_dir: PathBuf,
+        before_build: &snapshot::OutDirSnapshot,
+    ) -> io::Result<()> {
+        let entries = match std::fs::read_dir(&out_dir) {
+            Ok(entries) => entries,
+            // wasm-pack can succeed without building, like with `--help`.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                self.out_dir = Some(out_dir);
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        };
+
+        let mut generated_files = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let modified = entry.metadata()?.modified().ok();
+            if before_build.was_written(&entry.path(), modified) {
+                generated_files.push(entry.path());
+            }
+        }
+        generated_files.sort();
+
+        let mut wasm_size = 0;
+        for path in generated_files.iter().filter(|path| gzip::is_bg_wasm(path)) {
+            wasm_size += std::fs::metadata(path)?.len();
+        }
+
+        self.out_dir = Some(out_dir);
+        self.generated_files = generated_files;
+        self.wasm_size = wasm_size;
+        Ok(())
+    }
+
+    pub(crate) fn set_profile(&mut self, profile: Option<BuildProfile>) {
+        self.profile = profile;
+    }
+
+    pub(crate) fn set_output_name(&mut self, output_name: Option<String>) {
+        self.output_name = output_name;
+    }
+
+    pub(crate) fn set_out_dir_guard(&mut self, guard: Option<out_dir_guard::OutDirGuard>) {
+        self.out_dir_guard = guard;
+    }
+
+    pub(crate) fn set_gzipped_wasm(&mut self, gzipped_wasm: Vec<GzippedWasm>) {
+        self.gzipped_wasm = gzipped_wasm;
     }
 
     pub fn stdout(&self) -> &str {
@@ -78,164 +701,857 @@ impl WasmPackOutput {
     pub fn stderr(&self) -> &str {
         &self.stderr
     }
+
+    /// A short ID for this run, which starts every log line about it, like
+    /// `[run 1a2b3c4d]`.
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// The platform of the embedded executable that ran. `None` if a custom
+    /// executable ran instead.
+    pub fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+
+    /// How long the run took, including extracting the executable.
+    pub fn duration(&self) -> std::time::Duration {
+        self.duration
+    }
+
+    /// How long it took to extract the embedded executable. Close to zero when
+    /// a cached, extracted or custom executable was used.
+    pub fn extraction_duration(&self) -> std::time::Duration {
+        self.extraction_duration
+    }
+
+    /// How long wasm-pack itself ran, excluding extraction.
+    pub fn execution_duration(&self) -> std::time::Duration {
+        self.execution_duration
+    }
+
+    /// The permission bits of the embedded executable that ran, like `0o755`,
+    /// read back from the file just before running it. `None` on Windows, and
+    /// when a custom executable ran.
+    pub fn executable_permissions(&self) -> Option<u32> {
+        self.executable_permissions
+    }
+
+    /// Whether this run reused the executable cached by
+    /// [`WasmPack::cache_executable`] instead of extracting it. Always `false`
+    /// without caching, and when a custom or already extracted executable ran.
+    pub fn extracted_from_cache(&self) -> bool {
+        self.extracted_from_cache
+    }
+
+    /// Where a build put its output, relative to the current directory. Only
+    /// known for builds run through the [`WasmPack`] builder.
+    pub fn out_dir(&self) -> Option<&Path> {
+        self.out_dir.as_deref()
+    }
+
+    /// The files in [`out_dir`](Self::out_dir) that the build wrote, sorted.
+    ///
+    /// Leftovers from earlier builds, like another crate's output, aren't
+    /// included. To tell them apart, the out-dir's modification times are
+    /// recorded before the build, and only files that are new or were
+    /// modified since count. Files modified in the second before the build
+    /// started count too, since some file systems only store whole seconds.
+    pub fn generated_files(&self) -> &[PathBuf] {
+        &self.generated_files
+    }
+
+    /// The name that starts the build's file names, like `my_crate` for
+    /// `my_crate.js` and `my_crate_bg.wasm`. That's the `--out-name` if one
+    /// was passed, and otherwise the crate's lib name with dashes replaced by
+    /// underscores. Only known for builds run through the [`WasmPack`]
+    /// builder.
+    pub fn output_name(&self) -> Option<&str> {
+        self.output_name.as_deref()
+    }
+
+    /// Keep the out-dir of an [`ephemeral`](WasmPack::ephemeral) build, rather
+    /// than deleting it when this is dropped. Does nothing for other runs.
+    pub fn persist(&mut self) {
+        if let Some(guard) = self.out_dir_guard.take() {
+            std::mem::forget(guard);
+        }
+    }
+
+    /// The total size in bytes of the `*_bg.wasm` files in
+    /// [`out_dir`](Self::out_dir) after the build.
+    pub fn wasm_size(&self) -> u64 {
+        self.wasm_size
+    }
+
+    /// The profile wasm-pack built with, worked out from the arguments.
+    /// `None` for subcommands that don't build, like `pack`.
+    pub fn profile(&self) -> Option<BuildProfile> {
+        self.profile
+    }
+
+    /// The version of the wasm-pack executable that ran, like `"0.12.1"`.
+    ///
+    /// For the embedded executables this is [`WASM_PACK_VERSION`]. For a
+    /// custom executable, it's found by running it with `--version` the first
+    /// time this is called.
+    pub fn executed_version(&self) -> io::Result<String> {
+        if let Some(version) = self.executed_version.get() {
+            return Ok(version.clone());
+        }
+
+        let version = match &self.custom_executable {
+            Some(executable) => {
+                let stdout = duct::cmd!(executable, "--version").read()?;
+                match parse_version_output(&stdout) {
+                    Some(version) => version.to_string(),
+                    None => stdout.trim().to_string(),
+                }
+            }
+            None => WASM_PACK_VERSION.to_string(),
+        };
+
+        Ok(self.executed_version.get_or_init(|| version).clone())
+    }
+
+    /// The warnings wasm-pack and cargo printed to stderr, one per line.
+    /// Cargo's "generated N warnings" summaries aren't included.
+    pub fn warnings(&self) -> Vec<&str> {
+        parse_warnings(&self.stderr)
+    }
+
+    /// Consume the output, returning the captured stdout and stderr without
+    /// copying them.
+    pub fn into_parts(self) -> (String, String) {
+        (self.stdout, self.stderr)
+    }
+
+    /// Consume the output, returning the captured stdout.
+    pub fn into_stdout(self) -> String {
+        self.stdout
+    }
+
+    /// Consume the output, returning the captured stderr.
+    pub fn into_stderr(self) -> String {
+        self.stderr
+    }
+
+    /// Whether stdout contained invalid UTF-8 that was replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn stdout_was_lossy(&self) -> bool {
+        self.stdout_was_lossy
+    }
+
+    /// Whether stderr contained invalid UTF-8 that was replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn stderr_was_lossy(&self) -> bool {
+        self.stderr_was_lossy
+    }
+
+    /// Whether stdout or stderr was longer than
+    /// [`WasmPack::max_output_bytes`] and had its middle cut out.
+    pub fn was_truncated(&self) -> bool {
+        self.was_truncated
+    }
+
+    /// The `.wasm.gz` files written by [`WasmPack::gzip_output`], along with
+    /// their sizes. Empty unless gzipping was enabled.
+    pub fn gzipped_wasm(&self) -> &[GzippedWasm] {
+        &self.gzipped_wasm
+    }
+}
+
+/// A one-line summary. Use `Debug` to see everything, including the captured
+/// output.
+impl Display for WasmPackOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "wasm-pack finished in {:.2?}", self.duration)?;
+
+        match (self.platform, &self.custom_executable) {
+            (Some(platform), _) => write!(f, " on {}", platform)?,
+            (None, Some(executable)) => write!(f, " using {}", executable.display())?,
+            (None, None) => {}
+        }
+
+        if let Some(out_dir) = &self.out_dir {
+            let file_count = self.generated_files.len();
+            let noun = if file_count == 1 { "file" } else { "files" };
+            write!(f, ", {} {} in {}", file_count, noun, out_dir.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_warnings(stderr: &str) -> Vec<&str> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("warning:") || line.starts_with("[WARN]:"))
+        .filter(|line| !is_warning_summary(line))
+        .collect()
+}
+
+/// Whether `line` is like "warning: `crate` (lib) generated 2 warnings".
+fn is_warning_summary(line: &str) -> bool {
+    let Some((_, count)) = line.rsplit_once(" generated ") else {
+        return false;
+    };
+
+    count
+        .trim_end_matches(" (run `cargo fix` to apply suggestions)")
+        .split_once(' ')
+        .is_some_and(|(number, noun)| {
+            number.parse::<usize>().is_ok() && (noun == "warning" || noun == "warnings")
+        })
+}
+
+/// Whether cargo finished without compiling anything, going by the
+/// "Compiling" lines it prints for each crate it builds. wasm-pack's own
+/// "[INFO]: Compiling to Wasm..." doesn't count.
+pub(crate) fn compiled_nothing(stderr: &str) -> bool {
+    let mut finished = false;
+    for line in stderr.lines().map(str::trim) {
+        if line.starts_with("Compiling ") {
+            return false;
+        }
+        finished |= line.starts_with("Finished ");
+    }
+    finished
+}
+
+struct CapturedOutput {
+    stdout: String,
+    stderr: String,
+    stdout_was_lossy: bool,
+    stderr_was_lossy: bool,
+    was_truncated: bool,
 }
 
 fn get_stdout_and_stderr_from_process_output(
-    process_output: &std::process::Output,
-) -> (String, String) {
-    let stdout = String::from_utf8_lossy(&process_output.stdout)
-        .trim()
-        .to_string();
+    process_output: std::process::Output,
+    max_output_bytes: Option<usize>,
+) -> CapturedOutput {
+    // Runners are asked to cap their output, but don't have to.
+    let stdout = capture::cap(process_output.stdout, max_output_bytes);
+    let stderr = capture::cap(process_output.stderr, max_output_bytes);
+    let was_truncated = capture::is_truncated(&stdout, max_output_bytes)
+        || capture::is_truncated(&stderr, max_output_bytes);
 
-    let stderr = String::from_utf8_lossy(&process_output.stderr)
-        .trim()
-        .to_string();
+    let (stdout, stdout_was_lossy) = decode_lossily(&stdout);
+    let (stderr, stderr_was_lossy) = decode_lossily(&stderr);
 
-    (stdout, stderr)
+    CapturedOutput {
+        stdout,
+        stderr,
+        stdout_was_lossy,
+        stderr_was_lossy,
+        was_truncated,
+    }
 }
 
-fn get_cli_executable_file() -> Result<PathBuf, WasmPackError> {
-    let platform = guess_platform();
-    println!("Guessed platform: {:?}", platform);
-    let cli_executable_bytes = get_cli_executable_bytes(&platform);
-    println!(
+/// The signal that killed the process, if one did. Always `None` outside Unix,
+/// where there are no signals.
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        std::os::unix::process::ExitStatusExt::signal(status)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
+
+/// Decode `bytes` as UTF-8, replacing invalid sequences. Also returns whether
+/// anything had to be replaced.
+fn decode_lossily(bytes: &[u8]) -> (String, bool) {
+    let decoded = String::from_utf8_lossy(bytes);
+    let was_lossy = matches!(decoded, std::borrow::Cow::Owned(_));
+    (decoded.trim().to_string(), was_lossy)
+}
+
+/// Extract the embedded executable, or reuse the cached one. Returns its path,
+/// and whether it came from the cache.
+fn get_cli_executable_file(options: &RunOptions) -> Result<(PathBuf, bool), WasmPackError> {
+    let platform = Platform::current()?;
+    run_log!(debug, "Guessed platform: {:?}", platform);
+
+    let target_dir = std::env::current_dir()
+        .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?
+        .join("target");
+
+    let cli_executable_bytes = platform::get_cli_executable_bytes(platform, &target_dir)?;
+    let cli_executable_bytes = &*cli_executable_bytes;
+    run_log!(
+        debug,
         "Got CLI executable bytes: {} bytes",
         cli_executable_bytes.len()
     );
 
-    // We use a UUID in case multiple builds are running at the same time.
-    let uuid = uuid::Uuid::new_v4().to_string();
-    let temp_file_name = format!("wasm-pack-{}-v{}-{}", platform, CRATE_VERSION, uuid);
-    let temp_file_path = std::env::current_dir()
-        .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?
-        .join("target")
-        .join(temp_file_name);
+    let forced_by_env = forces_extract(std::env::var_os(FORCE_EXTRACT_ENV_VAR).as_deref());
+    if forced_by_env {
+        run_log!(
+            info,
+            "{} is set, so the CLI executable is extracted fresh.",
+            FORCE_EXTRACT_ENV_VAR
+        );
+    }
 
-    let mut temp_file = std::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(&temp_file_path)
-        .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?;
-    println!("Created temporary file: {:?}", &temp_file_path);
+    if options.cache_executable {
+        let cached_file_path = target_dir.join(cached_file_name(platform));
+        let cached_file_path =
+            paths::extended_length_path(&cached_file_path).unwrap_or(cached_file_path);
 
-    temp_file
-        .write_all(cli_executable_bytes)
+        // A cached executable with other permissions is written again, since
+        // the mode may have been tightened since it was cached.
+        let mode = options.executable_mode.unwrap_or(DEFAULT_EXECUTABLE_MODE);
+        let mode_matches = permission_bits(&cached_file_path).is_none_or(|bits| bits == mode);
+        if !options.force_extract
+            && !forced_by_env
+            && mode_matches
+            && cached_file_matches(&cached_file_path, cli_executable_bytes)
+                .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?
+        {
+            run_log!(
+                debug,
+                "Reusing cached CLI executable: {:?}",
+                &cached_file_path
+            );
+            return Ok((cached_file_path, true));
+        }
+
+        replace_atomically(&cached_file_path, |partial_file_path| {
+            write_cli_executable(
+                partial_file_path,
+                cli_executable_bytes,
+                mode,
+                options.strict_permissions,
+            )
+        })
         .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?;
-    println!("Wrote CLI executable bytes to temporary file.");
+        return Ok((cached_file_path, false));
+    }
 
-    // Make the file executable. This isn't supported on Windows, so we skip it.
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut permissions = temp_file
-            .metadata()
-            .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?
-            .permissions();
-        // 755 - owner can read/write/execute, group/others can read/execute.
-        permissions.set_mode(0o755);
-        temp_file
-            .set_permissions(permissions)
-            .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?;
-        println!("Made temporary file executable.");
+    let temp_file_path = target_dir.join(temp_file_name(platform, options));
+    let temp_file_path = paths::extended_length_path(&temp_file_path).unwrap_or(temp_file_path);
+
+    write_cli_executable(
+        &temp_file_path,
+        cli_executable_bytes,
+        options.executable_mode.unwrap_or(DEFAULT_EXECUTABLE_MODE),
+        options.strict_permissions,
+    )
+    .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?;
+    Ok((temp_file_path, false))
+}
+
+/// Whether `value`, from [`FORCE_EXTRACT_ENV_VAR`], turns the override on.
+fn forces_extract(value: Option<&std::ffi::OsStr>) -> bool {
+    value.is_some_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+fn temp_file_name(platform: Platform, options: &RunOptions) -> String {
+    let suffix = match &options.temp_name_suffix {
+        Some(suffix) => (suffix.0)(),
+        // We use a UUID in case multiple builds are running at the same time.
+        // This is the run ID when there is one, so the file can be matched up
+        // with the logs.
+        None => run_id::current()
+            .unwrap_or_else(uuid::Uuid::new_v4)
+            .to_string(),
+    };
+    format!("wasm-pack-{}-v{}-{}", platform, CRATE_VERSION, suffix)
+}
+
+pub(crate) fn cached_file_name(platform: Platform) -> String {
+    format!("wasm-pack-{}-v{}", platform, CRATE_VERSION)
+}
+
+/// Whether the cached executable at `path` is exactly `expected_bytes`.
+///
+/// The length check catches most partial writes without reading the file.
+/// Comparing the contents catches the rest, plus any tampering.
+fn cached_file_matches(path: &Path, expected_bytes: &[u8]) -> io::Result<bool> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error),
+    };
+
+    if metadata.len() != expected_bytes.len() as u64 {
+        run_log!(debug, "Cached CLI executable has the wrong length.");
+        return Ok(false);
     }
 
-    // Make sure the file is closed and written to disk.
-    temp_file
-        .sync_all()
-        .map_err(WasmPackError::CouldntSaveCliExecutableToTemporaryFile)?;
-    drop(temp_file);
+    let cached_bytes = std::fs::read(path)?;
+    let matches = cached_bytes == expected_bytes;
+    if !matches {
+        run_log!(debug, "Cached CLI executable has the wrong contents.");
+    }
 
-    Ok(temp_file_path)
+    Ok(matches)
 }
 
-#[derive(Debug)]
-enum Platform {
-    MacOs,
+/// Create the file at `path` with `write`, so that other processes see either
+/// the old file or the complete new one, never a partial write.
+///
+/// `write` is given a uniquely named file next to `path`, which is renamed over
+/// `path` once it's complete.
+fn replace_atomically(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let uuid = uuid::Uuid::new_v4();
+    let partial_file_path = path.with_file_name(format!("{}.{}.partial", file_name, uuid));
+
+    let result = write(&partial_file_path).and_then(|()| {
+        match std::fs::rename(&partial_file_path, path) {
+            Ok(()) => Ok(()),
+            // Windows can't replace a file that's running, which happens when
+            // another process is using the cached executable. If that process
+            // wrote the same bytes we did, that's fine.
+            Err(error) if cfg!(windows) && error.kind() == io::ErrorKind::PermissionDenied => {
+                let expected_bytes = std::fs::read(&partial_file_path)?;
+                match cached_file_matches(path, &expected_bytes)? {
+                    true => Ok(()),
+                    false => Err(error),
+                }
+            }
+            Err(error) => Err(error),
+        }
+    });
 
-    LinuxArm64,
-    LinuxX64,
+    if partial_file_path.exists() {
+        let _ignore_errors = std::fs::remove_file(&partial_file_path);
+    }
 
-    Windows,
+    result
 }
 
-impl std::fmt::Display for Platform {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            Platform::MacOs => "x86_64-apple-darwin",
-            Platform::LinuxArm64 => "aarch64-unknown-linux-musl",
-            Platform::LinuxX64 => "x86_64-unknown-linux-musl",
-            Platform::Windows => "x86_64-pc-windows-msvc",
-        };
-        write!(f, "{}", name)
+/// The permission bits extracted executables get on Unix, unless set with
+/// [`WasmPack::executable_mode`]: the owner can read, write and execute, and
+/// everyone else can read and execute.
+pub(crate) const DEFAULT_EXECUTABLE_MODE: u32 = 0o755;
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn write_cli_executable(
+    temp_file_path: &Path,
+    cli_executable_bytes: &[u8],
+    mode: u32,
+    strict_permissions: bool,
+) -> io::Result<()> {
+    let mut open_options = std::fs::OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+    // Only the owner can touch the file until it's fully written, so nobody
+    // else can swap in their own bytes in the meantime.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut open_options, 0o700);
+    let mut temp_file = open_options.open(temp_file_path)?;
+    run_log!(debug, "Created temporary file: {:?}", &temp_file_path);
+
+    temp_file.write_all(cli_executable_bytes)?;
+    run_log!(debug, "Wrote CLI executable bytes to temporary file.");
+
+    // Make the file executable, now that it's complete. This isn't supported on
+    // Windows, so we skip it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = temp_file.metadata()?.permissions();
+        permissions.set_mode(mode);
+        let result = temp_file.set_permissions(permissions);
+        allow_permissions_error(result, strict_permissions)?;
     }
+
+    // Make sure the file is closed and written to disk.
+    temp_file.sync_all()?;
+    drop(temp_file);
+
+    Ok(())
 }
 
-fn guess_platform() -> Platform {
-    let os = std::env::consts::OS;
-    let arch = std::env::consts::ARCH;
+/// The permission bits of the file at `path`, or `None` on platforms without
+/// them.
+fn permission_bits(path: &Path) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(metadata.permissions().mode() & 0o7777)
+    }
 
-    match os {
-        "macos" => Platform::MacOs,
-        "linux" => match arch {
-            "x86_64" => Platform::LinuxX64,
-            "aarch64" => Platform::LinuxArm64,
-            _ => panic!("Unsupported architecture: {}", arch),
-        },
-        "windows" => Platform::Windows,
-        _ => panic!("Unsupported OS: {}", os),
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
     }
 }
 
-fn get_cli_executable_bytes(platform: &Platform) -> &'static [u8] {
-    match platform {
-        Platform::MacOs => include_bytes!("./wasm-pack-v0.12.1-x86_64-apple-darwin/wasm-pack"),
-        Platform::LinuxArm64 => {
-            include_bytes!("./wasm-pack-v0.12.1-aarch64-unknown-linux-musl/wasm-pack")
-        }
-        Platform::LinuxX64 => {
-            include_bytes!("./wasm-pack-v0.12.1-x86_64-unknown-linux-musl/wasm-pack")
+/// Some filesystems, like certain network mounts, refuse to change permissions
+/// even though the file can be executed anyway. Unless we're being strict, we
+/// carry on, and running the executable will fail if it really can't run.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn allow_permissions_error(result: io::Result<()>, strict_permissions: bool) -> io::Result<()> {
+    match result {
+        Ok(()) => {
+            run_log!(debug, "Made temporary file executable.");
+            Ok(())
         }
-        Platform::Windows => {
-            include_bytes!("./wasm-pack-v0.12.1-x86_64-pc-windows-msvc/wasm-pack.exe")
+        Err(error) if !strict_permissions => {
+            run_log!(
+                warn,
+                "Couldn't make the wasm-pack executable executable, trying to run it anyway: {}",
+                error
+            );
+            Ok(())
         }
+        Err(error) => Err(error),
     }
 }
 
 #[derive(Debug)]
 pub enum WasmPackError {
-    WasmPackReturnedAnError { stdout: String, stderr: String },
+    /// wasm-pack exited unsuccessfully. `stdout` and `stderr` are complete,
+    /// but only the last `tail_lines` lines of each are displayed.
+    WasmPackReturnedAnError {
+        stdout: String,
+        stderr: String,
+        tail_lines: usize,
+    },
+    /// wasm-pack was killed by `signal` instead of exiting, like by the
+    /// out-of-memory killer's `SIGKILL`. Only happens on Unix. The output is
+    /// what wasm-pack printed before it was killed.
+    KilledBySignal {
+        signal: i32,
+        stdout: String,
+        stderr: String,
+        tail_lines: usize,
+    },
+    /// wasm-pack, or something it ran, ran out of memory under the limit set
+    /// with [`WasmPack::memory_limit`].
+    MemoryLimitExceeded {
+        limit: u64,
+        stdout: String,
+        stderr: String,
+        tail_lines: usize,
+    },
     CouldntInvokeWasmPack(io::Error),
+    CouldntCreateLogFile(io::Error),
+    Interrupted,
     CouldntSaveCliExecutableToTemporaryFile(io::Error),
     CouldntDeleteTemporaryFile(io::Error),
+    CouldntExtractCliExecutable(io::Error),
+    CouldntDownloadWasmPack(io::Error),
+    CouldntCheckForUpdates(io::Error),
+    CouldntReadCargoMetadata(io::Error),
+    WorkspaceMemberNotFound {
+        name: String,
+    },
+    WorkspaceMemberIsntACdylib {
+        name: String,
+    },
+    CouldntGzipOutput(io::Error),
+    CouldntTarOutput(io::Error),
+    CouldntReadOutDir(io::Error),
+    /// A generated `*_bg.wasm` file isn't valid WebAssembly.
+    CouldntParseWasm(io::Error),
+    CouldntWriteBuildInfo(io::Error),
+    CouldntUseToolCacheDir(io::Error),
+    ManifestPathIsntCargoToml {
+        path: PathBuf,
+    },
+    ManifestNotFound {
+        path: PathBuf,
+    },
+    UnsupportedPlatform {
+        os: String,
+        arch: String,
+    },
+    PlatformNotBundled {
+        platform: Platform,
+    },
+    WasmBindgenNotFound,
+    InvalidConfiguration(String),
+    UnsupportedOption {
+        option: String,
+        reason: String,
+    },
+    WarningsDenied {
+        warnings: Vec<String>,
+    },
+    UnexpectedOutput {
+        reason: String,
+    },
+    /// The generated `*_bg.wasm` files add up to more than the size budget.
+    /// `gzipped` says whether the budget was for their gzipped size.
+    BudgetExceeded {
+        actual: u64,
+        budget: u64,
+        gzipped: bool,
+    },
+    TooManyImports {
+        stats: WasmStats,
+        max_imports: usize,
+    },
+}
+
+impl WasmPackError {
+    /// If wasm-pack returned an error for a reason we recognize, what it was.
+    pub fn failure(&self) -> Option<WasmPackFailure> {
+        match self {
+            WasmPackError::WasmPackReturnedAnError { stderr, .. } => {
+                WasmPackFailure::from_stderr(stderr)
+            }
+            _ => None,
+        }
+    }
+
+    /// The compiler errors and warnings in wasm-pack's stdout, if it returned
+    /// an error and the build had
+    /// [`message_format_json`](WasmPack::message_format_json) turned on.
+    pub fn cargo_messages(&self) -> Vec<CargoMessage> {
+        self.full_stdout()
+            .map(cargo_message::parse)
+            .unwrap_or_default()
+    }
+
+    /// All of wasm-pack's stdout, if it returned an error, even the lines
+    /// that aren't displayed.
+    pub fn full_stdout(&self) -> Option<&str> {
+        match self {
+            WasmPackError::WasmPackReturnedAnError { stdout, .. }
+            | WasmPackError::KilledBySignal { stdout, .. }
+            | WasmPackError::MemoryLimitExceeded { stdout, .. } => Some(stdout),
+            _ => None,
+        }
+    }
+
+    /// All of wasm-pack's stderr, if it returned an error, even the lines
+    /// that aren't displayed.
+    pub fn full_stderr(&self) -> Option<&str> {
+        match self {
+            WasmPackError::WasmPackReturnedAnError { stderr, .. }
+            | WasmPackError::KilledBySignal { stderr, .. }
+            | WasmPackError::MemoryLimitExceeded { stderr, .. } => Some(stderr),
+            _ => None,
+        }
+    }
+}
+
+/// Write the last `n` lines of one of wasm-pack's streams, saying how many
+/// were left out.
+fn write_tail(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    text: &str,
+    n: usize,
+) -> std::fmt::Result {
+    let (tail, omitted) = capture::tail_lines(text, n);
+    writeln!(f, "{}:", name)?;
+    if omitted > 0 {
+        writeln!(f, "[... {} earlier lines omitted ...]", omitted)?;
+    }
+    write!(f, "{}\n\n", tail)
 }
 
 impl Display for WasmPackError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            WasmPackError::WasmPackReturnedAnError { stdout, stderr } => {
+            WasmPackError::WasmPackReturnedAnError {
+                stdout,
+                stderr,
+                tail_lines,
+            } => {
                 write!(f, "wasm-pack returned an error:\n\n")?;
-                write!(f, "stdout:\n{}\n\n", stdout)?;
-                write!(f, "stderr:\n{}\n\n", stderr)?;
+                write_tail(f, "stdout", stdout, *tail_lines)?;
+                write_tail(f, "stderr", stderr, *tail_lines)?;
+                if let Some(failure) = self.failure() {
+                    write!(f, "{}\n\n", failure)?;
+                }
                 Ok(())
             }
-            WasmPackError::CouldntInvokeWasmPack(error) => {
-                write!(f, "Couldn't invoke wasm-pack: {}", error)
+            WasmPackError::KilledBySignal {
+                signal,
+                stdout,
+                stderr,
+                tail_lines,
+            } => {
+                write!(f, "wasm-pack was killed by signal {}", signal)?;
+                #[cfg(unix)]
+                if *signal == libc::SIGKILL {
+                    write!(f, " (SIGKILL), maybe by the out-of-memory killer")?;
+                }
+                write!(f, ":\n\n")?;
+                write_tail(f, "stdout", stdout, *tail_lines)?;
+                write_tail(f, "stderr", stderr, *tail_lines)
+            }
+            WasmPackError::MemoryLimitExceeded {
+                limit,
+                stdout,
+                stderr,
+                tail_lines,
+            } => {
+                write!(
+                    f,
+                    "wasm-pack ran out of memory under the limit of {} bytes:\n\n",
+                    limit
+                )?;
+                write_tail(f, "stdout", stdout, *tail_lines)?;
+                write_tail(f, "stderr", stderr, *tail_lines)
+            }
+            WasmPackError::CouldntInvokeWasmPack(_) => {
+                write!(f, "Couldn't invoke wasm-pack")
+            }
+            WasmPackError::CouldntCreateLogFile(_) => {
+                write!(f, "Couldn't create a log file for wasm-pack's output")
+            }
+            WasmPackError::Interrupted => {
+                write!(f, "wasm-pack was stopped because we were interrupted")
+            }
+            WasmPackError::CouldntSaveCliExecutableToTemporaryFile(_) => {
+                write!(f, "Couldn't save wasm-pack executable to temporary file")
+            }
+            WasmPackError::CouldntDeleteTemporaryFile(_) => {
+                write!(f, "Couldn't delete temporary file")
+            }
+            WasmPackError::CouldntExtractCliExecutable(_) => {
+                write!(f, "Couldn't extract wasm-pack executable")
+            }
+            WasmPackError::CouldntDownloadWasmPack(_) => {
+                write!(f, "Couldn't download wasm-pack")
+            }
+            WasmPackError::CouldntCheckForUpdates(_) => {
+                write!(f, "Couldn't check for wasm-pack updates")
+            }
+            WasmPackError::CouldntReadCargoMetadata(_) => {
+                write!(f, "Couldn't read `cargo metadata`")
+            }
+            WasmPackError::WorkspaceMemberNotFound { name } => {
+                write!(f, "Couldn't find a workspace member called `{}`", name)
+            }
+            WasmPackError::WorkspaceMemberIsntACdylib { name } => {
+                write!(
+                    f,
+                    "Workspace member `{}` can't be built with wasm-pack because its crate-type doesn't include \"cdylib\"",
+                    name
+                )
+            }
+            WasmPackError::CouldntGzipOutput(_) => {
+                write!(f, "Couldn't gzip wasm-pack output")
+            }
+            WasmPackError::CouldntTarOutput(_) => {
+                write!(f, "Couldn't make a tarball of wasm-pack's output")
+            }
+            WasmPackError::CouldntReadOutDir(_) => {
+                write!(f, "Couldn't read wasm-pack's out-dir")
+            }
+            WasmPackError::CouldntParseWasm(error) => {
+                write!(f, "Couldn't parse the generated wasm: {}", error)
+            }
+            WasmPackError::CouldntWriteBuildInfo(_) => {
+                write!(f, "Couldn't write build info")
+            }
+            WasmPackError::CouldntUseToolCacheDir(_) => {
+                write!(f, "Couldn't write to the tool cache directory")
+            }
+            WasmPackError::ManifestPathIsntCargoToml { path } => {
+                write!(f, "Manifest path {:?} doesn't point at a Cargo.toml", path)
+            }
+            WasmPackError::ManifestNotFound { path } => {
+                write!(f, "Couldn't find a manifest at {:?}", path)
             }
-            WasmPackError::CouldntSaveCliExecutableToTemporaryFile(error) => {
+            WasmPackError::UnsupportedPlatform { os, arch } => {
                 write!(
                     f,
-                    "Couldn't save wasm-pack executable to temporary file: {}",
-                    error
+                    "There's no wasm-pack executable for this platform (OS: {}, architecture: {})",
+                    os, arch
                 )
             }
-            WasmPackError::CouldntDeleteTemporaryFile(error) => {
-                write!(f, "Couldn't delete temporary file: {}", error)
+            WasmPackError::PlatformNotBundled { platform } => match platform.feature_name() {
+                Some(feature_name) => write!(
+                    f,
+                    "The wasm-pack executable for {} isn't bundled. Enable lib_wasm_pack's `{}` feature to include it, or the `download` feature to download it when needed",
+                    platform, feature_name
+                ),
+                None => write!(
+                    f,
+                    "The wasm-pack executable for {} isn't bundled, because wasm-pack doesn't publish one",
+                    platform
+                ),
+            },
+            WasmPackError::WasmBindgenNotFound => {
+                write!(
+                    f,
+                    "Couldn't find `wasm-bindgen` on the PATH, which `--mode no-install` needs. Install it with `cargo install wasm-bindgen-cli`"
+                )
+            }
+            WasmPackError::InvalidConfiguration(reason) => {
+                write!(f, "Invalid wasm-pack configuration: {}", reason)
             }
+            WasmPackError::UnsupportedOption { option, reason } => {
+                write!(
+                    f,
+                    "wasm-pack {} doesn't support `{}`: {}",
+                    WASM_PACK_VERSION, option, reason
+                )
+            }
+            WasmPackError::UnexpectedOutput { reason } => {
+                write!(f, "wasm-pack produced unexpected output: {}", reason)
+            }
+            WasmPackError::WarningsDenied { warnings } => {
+                writeln!(f, "wasm-pack succeeded, but warnings are denied:")?;
+                for warning in warnings {
+                    writeln!(f, "{}", warning)?;
+                }
+                Ok(())
+            }
+            WasmPackError::BudgetExceeded {
+                actual,
+                budget,
+                gzipped,
+            } => {
+                let size = if *gzipped { "gzipped size" } else { "size" };
+                write!(
+                    f,
+                    "The generated .wasm's {} is {} bytes, which is over the budget of {} bytes",
+                    size, actual, budget
+                )
+            }
+            WasmPackError::TooManyImports { stats, max_imports } => write!(
+                f,
+                "The generated .wasm has {} imports, which is over the limit of {} ({})",
+                stats.imports, max_imports, stats
+            ),
         }
     }
 }
 
-impl std::error::Error for WasmPackError {}
+/// The underlying [`io::Error`] is available through [`source`], and isn't
+/// repeated in the [`Display`] message.
+///
+/// [`source`]: std::error::Error::source
+impl std::error::Error for WasmPackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WasmPackError::CouldntInvokeWasmPack(error)
+            | WasmPackError::CouldntCreateLogFile(error)
+            | WasmPackError::CouldntSaveCliExecutableToTemporaryFile(error)
+            | WasmPackError::CouldntDeleteTemporaryFile(error)
+            | WasmPackError::CouldntExtractCliExecutable(error)
+            | WasmPackError::CouldntDownloadWasmPack(error)
+            | WasmPackError::CouldntCheckForUpdates(error)
+            | WasmPackError::CouldntReadCargoMetadata(error)
+            | WasmPackError::CouldntGzipOutput(error)
+            | WasmPackError::CouldntTarOutput(error)
+            | WasmPackError::CouldntReadOutDir(error)
+            | WasmPackError::CouldntParseWasm(error)
+            | WasmPackError::CouldntWriteBuildInfo(error)
+            | WasmPackError::CouldntUseToolCacheDir(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -257,6 +1573,311 @@ mod tests {
         assert!(stdout.contains(&expected_version));
     }
 
+    #[test]
+    fn benchmarking_extraction_cleans_up() {
+        let (duration, path) = time_extraction().expect("Couldn't benchmark extraction.");
+        assert!(duration > std::time::Duration::ZERO);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn planning_the_temp_path() {
+        let platform = Platform::current().unwrap();
+        let path = planned_temp_path(platform);
+        assert!(path.is_absolute());
+        assert!(path.parent().unwrap().ends_with("target"));
+        let prefix = format!("wasm-pack-{}-v{}-", platform, CRATE_VERSION);
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(file_name.starts_with(&prefix));
+        assert!(!path.exists());
+
+        let options = RunOptions {
+            cache_executable: true,
+            ..RunOptions::default()
+        };
+        assert!(planned_extraction_path(platform, &options).ends_with(cached_file_name(platform)));
+    }
+
+    #[test]
+    fn extracting_to_a_chosen_path() {
+        let dir = Path::new("target/extract-to-test");
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(format!("wasm-pack{}", std::env::consts::EXE_SUFFIX));
+
+        // Extracting should replace whatever's already there.
+        let platform = Platform::current().unwrap();
+        let expected_bytes = bundled_binary(platform).unwrap();
+        std::fs::write(&path, vec![0u8; expected_bytes.len() + 1]).unwrap();
+
+        extract_to(&path, platform).expect("Couldn't extract CLI executable.");
+        assert!(std::fs::read(&path).unwrap() == expected_bytes);
+
+        let output = duct::cmd!(&path, "--version").read().unwrap();
+        assert!(output.starts_with("wasm-pack"));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn wasm_pack_version_matches_crate_version() {
+        // See the version policy in the README.
+        assert_eq!(CRATE_VERSION.split('-').next(), Some(WASM_PACK_VERSION));
+    }
+
+    #[test]
+    fn executed_version_of_a_custom_executable() {
+        let dir = Path::new("target/executed-version-test");
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(format!("wasm-pack{}", std::env::consts::EXE_SUFFIX));
+        extract_to(&path, Platform::current().unwrap()).unwrap();
+
+        let output = WasmPack::build("test-crate")
+            .executable(&path)
+            .raw_wasm_pack_arg("--help")
+            .run()
+            .expect("Couldn't run custom executable.");
+        assert_eq!(output.executed_version().unwrap(), WASM_PACK_VERSION);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn extracted_executables_end_up_world_executable_but_not_writable() {
+        let output = run(["--version"]).expect("Couldn't run wasm-pack.");
+        assert_eq!(output.executable_permissions(), Some(0o755));
+    }
+
+    #[test]
+    fn runs_have_ids() {
+        let first = run(["--version"]).expect("Couldn't run wasm-pack.");
+        let second = run(["--version"]).expect("Couldn't run wasm-pack.");
+        assert_eq!(first.run_id().len(), 8);
+        assert_ne!(first.run_id(), second.run_id());
+    }
+
+    #[test]
+    fn extraction_and_execution_are_timed_separately() {
+        let output = run(["--version"]).expect("Couldn't run wasm-pack.");
+        assert!(output.extraction_duration() > std::time::Duration::ZERO);
+        assert!(output.execution_duration() > std::time::Duration::ZERO);
+        assert!(output.extraction_duration() + output.execution_duration() <= output.duration());
+    }
+
+    #[test]
+    fn errors_only_display_the_last_lines() {
+        let stderr = (1..=100)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let error = WasmPackError::WasmPackReturnedAnError {
+            stdout: String::new(),
+            stderr: stderr.clone(),
+            tail_lines: 3,
+        };
+
+        let displayed = error.to_string();
+        assert!(
+            displayed.contains("[... 97 earlier lines omitted ...]\nline 98\nline 99\nline 100")
+        );
+        assert!(!displayed.contains("line 97"));
+        assert_eq!(error.full_stderr(), Some(stderr.as_str()));
+        assert_eq!(error.full_stdout(), Some(""));
+        assert_eq!(WasmPackError::Interrupted.full_stderr(), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn being_killed_by_a_signal_is_distinct_from_failing() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/killed-by-signal-test");
+        let script = fake_wasm_pack(dir, "#!/bin/sh\necho compiling\nkill -9 $$\n");
+
+        let error = WasmPack::login()
+            .executable(&script)
+            .run()
+            .expect_err("The script should have been killed.");
+        assert!(matches!(
+            &error,
+            WasmPackError::KilledBySignal { signal, stdout, .. }
+                if *signal == libc::SIGKILL && stdout == "compiling"
+        ));
+        assert!(error.to_string().contains("signal 9 (SIGKILL)"));
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn running_raw_returns_failures() {
+        let output = run_raw(["build", "target/no-such-crate"]).unwrap();
+        assert!(!output.success());
+        assert_eq!(output.code(), Some(1));
+        assert_eq!(output.signal(), None);
+        assert!(output.stderr().contains("no-such-crate"));
+
+        let output = run_raw(["--version"]).unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout(), format!("wasm-pack {}", WASM_PACK_VERSION));
+    }
+
+    #[test]
+    fn io_errors_are_sources() {
+        use std::error::Error;
+
+        let error = WasmPackError::CouldntReadOutDir(io::Error::other("disk on fire"));
+        assert_eq!(error.to_string(), "Couldn't read wasm-pack's out-dir");
+        assert_eq!(error.source().unwrap().to_string(), "disk on fire");
+
+        assert!(WasmPackError::WasmBindgenNotFound.source().is_none());
+    }
+
+    #[test]
+    fn parsing_warnings() {
+        let stderr = r#"[INFO]: Checking for the Wasm target...
+[INFO]: Compiling to Wasm...
+warning: function `set_panic_hook` is never used
+ --> src/utils.rs:1:8
+  |
+1 | pub fn set_panic_hook() {
+  |        ^^^^^^^^^^^^^^
+
+warning: `test-crate` (lib) generated 1 warning
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.05s
+[WARN]: :-) origin crate has no README
+[INFO]: :-) Done in 0.30s"#;
+
+        assert_eq!(
+            parse_warnings(stderr),
+            vec![
+                "warning: function `set_panic_hook` is never used",
+                "[WARN]: :-) origin crate has no README",
+            ]
+        );
+    }
+
+    #[test]
+    fn telling_whether_anything_was_compiled() {
+        let fresh = "[INFO]: Compiling to Wasm...
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.02s
+[INFO]: :-) Done in 0.08s";
+        assert!(compiled_nothing(fresh));
+
+        let rebuilt = "[INFO]: Compiling to Wasm...
+   Compiling test-crate v0.1.0 (/crate/test-crate)
+    Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.35s";
+        assert!(!compiled_nothing(rebuilt));
+
+        // Cargo never got as far as finishing.
+        assert!(!compiled_nothing(
+            "Error: crate directory is missing a `Cargo.toml` file"
+        ));
+    }
+
+    #[test]
+    fn lossy_decoding_is_detected() {
+        assert_eq!(decode_lossily(b" valid \n"), ("valid".to_string(), false));
+        assert_eq!(
+            decode_lossily(b"in\xffvalid"),
+            ("in\u{FFFD}valid".to_string(), true)
+        );
+    }
+
+    /// Held by tests that touch the cached executable, so one doesn't corrupt
+    /// it while another expects it to be reused.
+    static CACHED_EXECUTABLE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn corrupted_cached_executable_is_re_extracted() {
+        let _lock = CACHED_EXECUTABLE
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let options = RunOptions {
+            cache_executable: true,
+            ..RunOptions::default()
+        };
+
+        let (cached_file_path, _) =
+            get_cli_executable_file(&options).expect("Couldn't extract CLI executable.");
+        std::fs::write(&cached_file_path, b"corrupted").unwrap();
+
+        let (re_extracted_file_path, from_cache) =
+            get_cli_executable_file(&options).expect("Couldn't re-extract CLI executable.");
+        assert_eq!(re_extracted_file_path, cached_file_path);
+        assert!(!from_cache);
+
+        let expected_bytes = bundled_binary(Platform::current().unwrap()).unwrap();
+        let cached_bytes = std::fs::read(&cached_file_path).unwrap();
+        assert!(cached_bytes == expected_bytes);
+    }
+
+    #[test]
+    fn second_cached_run_reuses_the_executable() {
+        let _lock = CACHED_EXECUTABLE
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let run = || {
+            WasmPack::version()
+                .cache_executable(true)
+                .run()
+                .expect("Couldn't run `wasm-pack --version`.")
+                .into_output()
+        };
+
+        run();
+        assert!(run().extracted_from_cache());
+
+        let uncached = WasmPack::version()
+            .run()
+            .expect("Couldn't run `wasm-pack --version`.");
+        assert!(!uncached.into_output().extracted_from_cache());
+    }
+
+    #[test]
+    fn forcing_extraction_from_the_environment() {
+        let forces = |value: &str| forces_extract(Some(std::ffi::OsStr::new(value)));
+        assert!(forces("1"));
+        assert!(forces("true"));
+        assert!(forces("TRUE"));
+        assert!(!forces("0"));
+        assert!(!forces(""));
+        assert!(!forces_extract(None));
+    }
+
+    #[test]
+    fn permissions_errors_are_only_fatal_when_strict() {
+        let denied = || Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(allow_permissions_error(denied(), false).is_ok());
+        assert!(allow_permissions_error(denied(), true).is_err());
+        assert!(allow_permissions_error(Ok(()), true).is_ok());
+    }
+
+    #[test]
+    fn interrupted_writes_leave_the_old_file() {
+        let dir = Path::new("target/interrupted-write-test");
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("file");
+        std::fs::write(&path, b"old").unwrap();
+
+        let result = replace_atomically(&path, |partial_file_path| {
+            std::fs::write(partial_file_path, b"ne")?;
+            Err(io::Error::other("interrupted"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+
+        replace_atomically(&path, |partial_file_path| {
+            std::fs::write(partial_file_path, b"new")
+        })
+        .expect("Couldn't replace file.");
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+
+        // Partial files are always cleaned up.
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn building_a_crate() {
         let input_crate_path = "test-crate";
@@ -300,7 +1921,7 @@ export * from "./test_crate_bg.js";
 
         let result = run(&args);
 
-        if let Err(WasmPackError::WasmPackReturnedAnError { stdout, stderr }) = result {
+        if let Err(WasmPackError::WasmPackReturnedAnError { stdout, stderr, .. }) = result {
             assert!(stdout.is_empty());
             assert!(stderr.contains("Error: crate directory is missing a `Cargo.toml` file; is `fake-crate` the wrong directory?"));
         } else {
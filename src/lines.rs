@@ -0,0 +1,239 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+/// Which of wasm-pack's output streams a line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of wasm-pack's output, without its line ending.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+    pub stream: OutputStream,
+    pub text: String,
+}
+
+/// The last few lines of wasm-pack's output, updated while it runs, like for
+/// a scrolling log pane. Pass a clone to [`WasmPack::stream_into`](crate::WasmPack::stream_into)
+/// and read it from anywhere.
+///
+/// At most `capacity` lines are kept, so memory use is bounded by `capacity`
+/// times the length of the longest line.
+#[derive(Debug, Clone)]
+pub struct RecentLines {
+    capacity: usize,
+    lines: Arc<Mutex<VecDeque<OutputLine>>>,
+}
+
+impl RecentLines {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// Up to `n` of the most recent lines from either stream, oldest first.
+    pub fn recent_lines(&self, n: usize) -> Vec<OutputLine> {
+        let lines = self.lines.lock().unwrap_or_else(|error| error.into_inner());
+        let skip = lines.len().saturating_sub(n);
+        lines.iter().skip(skip).cloned().collect()
+    }
+
+    pub(crate) fn push(&self, line: &OutputLine) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lines = self.lines.lock().unwrap_or_else(|error| error.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.clone());
+    }
+}
+
+/// A function that's called with each line of output as it's read.
+#[derive(Clone)]
+pub(crate) struct LineCallback(pub(crate) Arc<dyn Fn(&OutputLine) + Send + Sync>);
+
+impl std::fmt::Debug for LineCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LineCallback")
+    }
+}
+
+/// The log target that [`logging_callback`] logs lines to.
+pub(crate) const OUTPUT_LOG_TARGET: &str = "lib_wasm_pack::output";
+
+/// Log each line at `level`, tagged with `run_id`, then pass it on to `next`.
+pub(crate) fn logging_callback(
+    level: log::Level,
+    run_id: String,
+    next: Option<LineCallback>,
+) -> LineCallback {
+    logging_callback_to(level, run_id, next, |record| {
+        if record.level() <= log::max_level() {
+            log::logger().log(record);
+        }
+    })
+}
+
+/// Like [`logging_callback`], but hands each record to `logger` rather than
+/// the global logger.
+fn logging_callback_to(
+    level: log::Level,
+    run_id: String,
+    next: Option<LineCallback>,
+    logger: impl Fn(&log::Record) + Send + Sync + 'static,
+) -> LineCallback {
+    LineCallback(Arc::new(move |line| {
+        let key_values = [
+            ("run_id", log::kv::Value::from(run_id.as_str())),
+            ("stream", log::kv::Value::from_debug(&line.stream)),
+        ];
+        logger(
+            &log::Record::builder()
+                .target(OUTPUT_LOG_TARGET)
+                .level(level)
+                .module_path_static(Some(module_path!()))
+                .file_static(Some(file!()))
+                .line(Some(line!()))
+                .key_values(&key_values)
+                .args(format_args!("[run {}] {}", run_id, line.text))
+                .build(),
+        );
+        if let Some(next) = &next {
+            (next.0)(line);
+        }
+    }))
+}
+
+/// Passes bytes through from `reader`, calling `callback` with each line.
+pub(crate) struct LineTee<R> {
+    reader: R,
+    stream: OutputStream,
+    callback: LineCallback,
+    partial_line: Vec<u8>,
+}
+
+impl<R> LineTee<R> {
+    pub(crate) fn new(reader: R, stream: OutputStream, callback: LineCallback) -> Self {
+        Self {
+            reader,
+            stream,
+            callback,
+            partial_line: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self) {
+        let mut bytes = std::mem::take(&mut self.partial_line);
+        if bytes.ends_with(b"\r") {
+            bytes.pop();
+        }
+        let line = OutputLine {
+            stream: self.stream,
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+        };
+        (self.callback.0)(&line);
+    }
+}
+
+impl<R: Read> Read for LineTee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        if read == 0 && !self.partial_line.is_empty() {
+            self.emit();
+        }
+
+        for &byte in &buf[..read] {
+            if byte == b'\n' {
+                self.emit();
+            } else {
+                self.partial_line.push(byte);
+            }
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitting_lines() {
+        let recent_lines = RecentLines::new(2);
+        let callback = LineCallback(Arc::new({
+            let recent_lines = recent_lines.clone();
+            move |line| recent_lines.push(line)
+        }));
+
+        let input = &b"one\r\ntwo\nthree\nfour"[..];
+        let mut tee = LineTee::new(input, OutputStream::Stderr, callback);
+        let mut output = Vec::new();
+        tee.read_to_end(&mut output).unwrap();
+        assert_eq!(output, input);
+
+        let line = |text: &str| OutputLine {
+            stream: OutputStream::Stderr,
+            text: text.to_string(),
+        };
+        assert_eq!(
+            recent_lines.recent_lines(5),
+            vec![line("three"), line("four")]
+        );
+        assert_eq!(recent_lines.recent_lines(1), vec![line("four")]);
+    }
+
+    #[test]
+    fn logging_lines() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let logger = {
+            let records = records.clone();
+            move |record: &log::Record| {
+                let run_id = record
+                    .key_values()
+                    .get("run_id".into())
+                    .map(|run_id| run_id.to_string())
+                    .unwrap_or_default();
+                let mut records = records.lock().unwrap();
+                records.push((
+                    record.level(),
+                    record.target().to_string(),
+                    record.args().to_string(),
+                    run_id,
+                ));
+            }
+        };
+
+        let recent_lines = RecentLines::new(1);
+        let next = LineCallback(Arc::new({
+            let recent_lines = recent_lines.clone();
+            move |line| recent_lines.push(line)
+        }));
+        let callback =
+            logging_callback_to(log::Level::Warn, "1a2b3c4d".to_string(), Some(next), logger);
+        let line = OutputLine {
+            stream: OutputStream::Stderr,
+            text: "warning: unused variable".to_string(),
+        };
+        (callback.0)(&line);
+
+        assert_eq!(
+            *records.lock().unwrap(),
+            vec![(
+                log::Level::Warn,
+                OUTPUT_LOG_TARGET.to_string(),
+                "[run 1a2b3c4d] warning: unused variable".to_string(),
+                "1a2b3c4d".to_string()
+            )]
+        );
+        assert_eq!(recent_lines.recent_lines(1), vec![line]);
+    }
+}
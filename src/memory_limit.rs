@@ -0,0 +1,84 @@
+/// Limit how much memory the process started by `expression`, and everything
+/// it starts in turn, can use. Only Linux is supported; elsewhere this warns
+/// and returns `expression` as it is.
+pub(crate) fn apply(expression: duct::Expression, bytes: u64) -> duct::Expression {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+
+        expression.before_spawn(move |command| {
+            // SAFETY: `setrlimit` is async-signal-safe, and nothing else runs
+            // between fork and exec.
+            unsafe {
+                command.pre_exec(move || {
+                    let limit = libc::rlimit {
+                        rlim_cur: bytes as libc::rlim_t,
+                        rlim_max: bytes as libc::rlim_t,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                })
+            };
+            Ok(())
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        log::warn!(
+            "Ignoring the memory limit of {} bytes, since it's only supported on Linux.",
+            bytes
+        );
+        expression
+    }
+}
+
+/// What processes print when an allocation fails.
+const OUT_OF_MEMORY_MARKERS: [&str; 4] = [
+    "memory allocation of",
+    "out of memory",
+    "cannot allocate memory",
+    "failed to allocate",
+];
+
+/// Whether a failed run looks like it ran out of memory, going by its stderr
+/// and the signal that killed it, if one did.
+pub(crate) fn was_exceeded(stderr: &str, signal: Option<i32>) -> bool {
+    let stderr = stderr.to_lowercase();
+    if OUT_OF_MEMORY_MARKERS
+        .iter()
+        .any(|marker| stderr.contains(marker))
+    {
+        return true;
+    }
+
+    // Running out of stack space under the limit is a segfault, and failing
+    // to allocate aborts Rust programs.
+    #[cfg(unix)]
+    if let Some(signal) = signal {
+        return [libc::SIGABRT, libc::SIGSEGV, libc::SIGKILL].contains(&signal);
+    }
+
+    let _ = signal;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizing_running_out_of_memory() {
+        assert!(was_exceeded(
+            "memory allocation of 1048576 bytes failed",
+            None
+        ));
+        assert!(was_exceeded(
+            "error: Cannot allocate memory (os error 12)",
+            None
+        ));
+        assert!(!was_exceeded("error: could not compile `test-crate`", None));
+    }
+}
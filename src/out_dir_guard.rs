@@ -0,0 +1,58 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Deletes a build's out-dir when it's dropped, for
+/// [`WasmPack::ephemeral`](crate::WasmPack::ephemeral) builds.
+#[derive(Debug)]
+pub(crate) struct OutDirGuard {
+    out_dir: PathBuf,
+}
+
+impl OutDirGuard {
+    /// Guard `out_dir`, unless it already exists, since then it isn't ours to
+    /// delete.
+    pub(crate) fn new(out_dir: PathBuf) -> Option<Self> {
+        if out_dir.exists() {
+            log::debug!(
+                "Leaving the out-dir {:?} in place afterwards, since it already exists.",
+                out_dir
+            );
+            return None;
+        }
+
+        Some(Self { out_dir })
+    }
+}
+
+impl Drop for OutDirGuard {
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.out_dir) {
+            Ok(()) => log::debug!("Deleted the out-dir {:?}.", self.out_dir),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => log::warn!("Couldn't delete the out-dir {:?}: {}", self.out_dir, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_deleting_new_out_dirs() {
+        let out_dir = PathBuf::from("target/out-dir-guard-test");
+        let _ignore_errors = std::fs::remove_dir_all(&out_dir);
+
+        let guard = OutDirGuard::new(out_dir.clone()).unwrap();
+        std::fs::create_dir_all(out_dir.join("snippets")).unwrap();
+        std::fs::write(out_dir.join("test_crate.js"), "").unwrap();
+        drop(guard);
+        assert!(!out_dir.exists());
+
+        std::fs::create_dir_all(&out_dir).unwrap();
+        assert!(OutDirGuard::new(out_dir.clone()).is_none());
+        assert!(out_dir.exists());
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+    }
+}
@@ -0,0 +1,125 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Work out the name wasm-pack gave a build's files, like `test_crate` for
+/// `test_crate.js` and `test_crate_bg.wasm`.
+///
+/// An `--out-name` in `args` wins. Otherwise the name comes from the generated
+/// `package.json`, and failing that, from the crate's `Cargo.toml`, the way
+/// wasm-pack works it out.
+pub(crate) fn resolve(
+    args: &[OsString],
+    generated_files: &[PathBuf],
+    input_crate: &Path,
+) -> Option<String> {
+    out_name_arg(args)
+        .or_else(|| from_package_json(generated_files))
+        .or_else(|| from_cargo_toml(input_crate))
+}
+
+/// The value of `--out-name`, ignoring anything passed on to cargo.
+fn out_name_arg(args: &[OsString]) -> Option<String> {
+    let mut args = args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--out-name" {
+            return args.next().map(|name| name.into_owned());
+        }
+        if let Some(name) = arg.strip_prefix("--out-name=") {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+/// The name of the JS file that a generated `package.json` points to.
+fn from_package_json(generated_files: &[PathBuf]) -> Option<String> {
+    let path = generated_files
+        .iter()
+        .find(|path| path.file_name().is_some_and(|name| name == "package.json"))?;
+    let package: serde_json::Value = serde_json::from_slice(&std::fs::read(path).ok()?).ok()?;
+
+    // `main` for Node.js, `module` for the other targets.
+    ["main", "module"].iter().find_map(|field| {
+        let entry = package[field].as_str()?;
+        entry.strip_suffix(".js").map(String::from)
+    })
+}
+
+/// The crate's lib name, or its package name, with dashes replaced by
+/// underscores, like rustc does.
+fn from_cargo_toml(input_crate: &Path) -> Option<String> {
+    let manifest = std::fs::read_to_string(input_crate.join("Cargo.toml")).ok()?;
+    let name = table_name(&manifest, "lib").or_else(|| table_name(&manifest, "package"))?;
+    Some(name.replace('-', "_"))
+}
+
+/// The `name` key of the `[table]` table in `manifest`. Only handles the
+/// simple `name = "..."` form, which is all a crate's name ever needs.
+fn table_name(manifest: &str, table: &str) -> Option<String> {
+    let header = format!("[{}]", table);
+    let mut in_table = false;
+
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_table = line == header;
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == "name" {
+            let value = value.trim();
+            return value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .map(String::from);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_out_name_from_args() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            out_name_arg(&args(&["build", "--out-name", "app", "crate"])),
+            Some("app".to_string())
+        );
+        assert_eq!(
+            out_name_arg(&args(&["build", "--out-name=app", "crate"])),
+            Some("app".to_string())
+        );
+        assert_eq!(
+            out_name_arg(&args(&["build", "crate", "--", "--out-name", "app"])),
+            None
+        );
+    }
+
+    #[test]
+    fn reading_the_name_from_cargo_toml() {
+        assert_eq!(
+            from_cargo_toml(Path::new("test-crate")),
+            Some("test_crate".to_string())
+        );
+
+        let manifest = "[package]\nname = \"my-crate\"\n\n[lib]\nname = \"my_lib\"\n";
+        assert_eq!(table_name(manifest, "lib"), Some("my_lib".to_string()));
+        assert_eq!(
+            table_name(manifest, "package"),
+            Some("my-crate".to_string())
+        );
+        assert_eq!(table_name(manifest, "bin"), None);
+    }
+}
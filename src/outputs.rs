@@ -0,0 +1,307 @@
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    parse_version_output, BuildProfile, CargoMessage, GzippedWasm, Version, WasmPackOutput,
+    WasmStats,
+};
+
+/// What a successful `wasm-pack build` returns, from
+/// [`WasmPack::build`](crate::WasmPack::build).
+#[derive(Debug)]
+pub struct BuildOutput {
+    output: WasmPackOutput,
+}
+
+impl BuildOutput {
+    pub(crate) fn new(output: WasmPackOutput) -> Self {
+        Self { output }
+    }
+
+    pub fn stdout(&self) -> &str {
+        self.output.stdout()
+    }
+
+    pub fn stderr(&self) -> &str {
+        self.output.stderr()
+    }
+
+    /// See [`WasmPackOutput::warnings`].
+    pub fn warnings(&self) -> Vec<&str> {
+        self.output.warnings()
+    }
+
+    /// The compiler errors and warnings from cargo, when the build had
+    /// [`message_format_json`](crate::WasmPack::message_format_json) turned
+    /// on. They're parsed from [`stdout`](Self::stdout), which still has all
+    /// of cargo's JSON.
+    pub fn cargo_messages(&self) -> Vec<CargoMessage> {
+        crate::cargo_message::parse(self.stdout())
+    }
+
+    /// The profile wasm-pack built with.
+    pub fn profile(&self) -> BuildProfile {
+        // The builder always records one; release is wasm-pack's default.
+        self.output.profile().unwrap_or(BuildProfile::Release)
+    }
+
+    /// Where wasm-pack put the output, relative to the current directory.
+    pub fn out_dir(&self) -> &Path {
+        // The builder always records it; `pkg` is wasm-pack's default.
+        self.output
+            .out_dir()
+            .unwrap_or(Path::new(crate::builder::DEFAULT_OUT_DIR))
+    }
+
+    /// The files in [`out_dir`](Self::out_dir) that the build wrote, sorted.
+    /// See [`WasmPackOutput::generated_files`] for how leftovers from earlier
+    /// builds are left out.
+    pub fn generated_files(&self) -> &[PathBuf] {
+        self.output.generated_files()
+    }
+
+    /// The name that starts the generated files' names, so the `.wasm` is
+    /// `{name}_bg.wasm` and the entry JS is `{name}.js`. See
+    /// [`WasmPackOutput::output_name`].
+    pub fn output_name(&self) -> Option<String> {
+        self.output.output_name().map(String::from)
+    }
+
+    /// See [`WasmPackOutput::persist`].
+    pub fn persist(&mut self) {
+        self.output.persist()
+    }
+
+    /// The total size in bytes of the generated `*_bg.wasm` files.
+    pub fn wasm_size(&self) -> u64 {
+        self.output.wasm_size()
+    }
+
+    /// Every file in [`out_dir`](Self::out_dir) with its size in bytes,
+    /// largest first, like for a table of bundle sizes. The out-dir is read
+    /// when this is called, so files written after the build, like `.wasm.gz`
+    /// files, are included.
+    pub fn file_size_report(&self) -> io::Result<Vec<(PathBuf, u64)>> {
+        crate::size_report::file_sizes(self.out_dir())
+    }
+
+    /// Like [`file_size_report`](Self::file_size_report), with each file's
+    /// size after gzipping it at `level` (0 to 9) as well. The files are
+    /// compressed in memory, and nothing is written. Higher levels are an
+    /// [`io::ErrorKind::InvalidInput`] error.
+    pub fn gzipped_file_size_report(&self, level: u32) -> io::Result<Vec<(PathBuf, u64, u64)>> {
+        crate::size_report::gzipped_file_sizes(self.out_dir(), level)
+    }
+
+    /// Whether cargo had nothing to recompile, so the `.wasm` is the same as
+    /// last time, like to skip uploading the output again.
+    ///
+    /// This is a heuristic based on cargo's output: the build counts as a
+    /// no-op if cargo printed that it finished without printing a
+    /// "Compiling" line for any crate. wasm-pack still runs wasm-bindgen and
+    /// rewrites the files in the out-dir either way.
+    pub fn was_noop(&self) -> bool {
+        crate::compiled_nothing(self.stderr())
+    }
+
+    /// Whether wasm-pack ran wasm-opt on the generated `.wasm`, going by what
+    /// it logged. Always `false` when
+    /// [`wasm_pack_log_level`](crate::WasmPack::wasm_pack_log_level) is below
+    /// `Info`, since wasm-pack doesn't log it then.
+    pub fn wasm_opt_ran(&self) -> bool {
+        crate::wasm_opt::ran(self.stderr())
+    }
+
+    /// Whether any of the generated `*_bg.wasm` files kept DWARF debug info.
+    /// wasm-bindgen strips it unless the crate's `Cargo.toml` sets
+    /// `dwarf-debug-info = true`, even in dev builds.
+    pub fn has_debug_info(&self) -> io::Result<bool> {
+        crate::debug_info::any_has_dwarf(self.generated_files())
+    }
+
+    /// How many imports, exports and functions the generated `*_bg.wasm`
+    /// files have, for targets where those matter as much as the size. The
+    /// files are parsed when this is called.
+    pub fn wasm_stats(&self) -> io::Result<WasmStats> {
+        crate::wasm_stats::for_files(self.generated_files())
+    }
+
+    /// See [`WasmPackOutput::gzipped_wasm`].
+    pub fn gzipped_wasm(&self) -> &[GzippedWasm] {
+        self.output.gzipped_wasm()
+    }
+
+    /// See [`WasmPackOutput::was_truncated`].
+    pub fn was_truncated(&self) -> bool {
+        self.output.was_truncated()
+    }
+
+    /// See [`WasmPackOutput::executed_version`].
+    pub fn executed_version(&self) -> io::Result<String> {
+        self.output.executed_version()
+    }
+
+    /// See [`WasmPackOutput::run_id`].
+    pub fn run_id(&self) -> &str {
+        self.output.run_id()
+    }
+
+    /// How long the build took, including extracting the executable.
+    pub fn duration(&self) -> std::time::Duration {
+        self.output.duration()
+    }
+
+    /// Everything recorded about the run, including what isn't specific to
+    /// builds.
+    pub fn into_output(self) -> WasmPackOutput {
+        self.output
+    }
+}
+
+impl Display for BuildOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.output.fmt(f)
+    }
+}
+
+/// What a successful `wasm-pack test` returns, from
+/// [`WasmPack::test`](crate::WasmPack::test).
+#[derive(Debug)]
+pub struct TestOutput {
+    output: WasmPackOutput,
+}
+
+impl TestOutput {
+    pub(crate) fn new(output: WasmPackOutput) -> Self {
+        Self { output }
+    }
+
+    pub fn stdout(&self) -> &str {
+        self.output.stdout()
+    }
+
+    pub fn stderr(&self) -> &str {
+        self.output.stderr()
+    }
+
+    /// See [`WasmPackOutput::warnings`].
+    pub fn warnings(&self) -> Vec<&str> {
+        self.output.warnings()
+    }
+
+    /// The profile the tests were built with.
+    pub fn profile(&self) -> BuildProfile {
+        // The builder always records one; dev is wasm-pack's default.
+        self.output.profile().unwrap_or(BuildProfile::Dev)
+    }
+
+    /// See [`WasmPackOutput::was_truncated`].
+    pub fn was_truncated(&self) -> bool {
+        self.output.was_truncated()
+    }
+
+    /// See [`WasmPackOutput::run_id`].
+    pub fn run_id(&self) -> &str {
+        self.output.run_id()
+    }
+
+    /// How long the tests took, including extracting the executable.
+    pub fn duration(&self) -> std::time::Duration {
+        self.output.duration()
+    }
+
+    /// Everything recorded about the run, including what isn't specific to
+    /// tests.
+    pub fn into_output(self) -> WasmPackOutput {
+        self.output
+    }
+}
+
+impl Display for TestOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.output.fmt(f)
+    }
+}
+
+/// What a successful `wasm-pack pack` returns, from
+/// [`WasmPack::pack`](crate::WasmPack::pack).
+#[derive(Debug)]
+pub struct PackOutput {
+    output: WasmPackOutput,
+}
+
+impl PackOutput {
+    pub(crate) fn new(output: WasmPackOutput) -> Self {
+        Self { output }
+    }
+
+    pub fn stdout(&self) -> &str {
+        self.output.stdout()
+    }
+
+    pub fn stderr(&self) -> &str {
+        self.output.stderr()
+    }
+
+    /// See [`WasmPackOutput::run_id`].
+    pub fn run_id(&self) -> &str {
+        self.output.run_id()
+    }
+
+    /// How long packing took, including extracting the executable.
+    pub fn duration(&self) -> std::time::Duration {
+        self.output.duration()
+    }
+
+    /// Everything recorded about the run.
+    pub fn into_output(self) -> WasmPackOutput {
+        self.output
+    }
+}
+
+impl Display for PackOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.output.fmt(f)
+    }
+}
+
+/// What a successful `wasm-pack --version` returns, from
+/// [`WasmPack::version`](crate::WasmPack::version).
+#[derive(Debug)]
+pub struct VersionOutput {
+    output: WasmPackOutput,
+}
+
+impl VersionOutput {
+    pub(crate) fn new(output: WasmPackOutput) -> Self {
+        Self { output }
+    }
+
+    /// The version wasm-pack printed. `None` if it couldn't be parsed, which
+    /// can happen with custom executables; the raw text is in
+    /// [`stdout`](Self::stdout).
+    pub fn version(&self) -> Option<Version> {
+        parse_version_output(self.output.stdout())
+    }
+
+    pub fn stdout(&self) -> &str {
+        self.output.stdout()
+    }
+
+    /// See [`WasmPackOutput::run_id`].
+    pub fn run_id(&self) -> &str {
+        self.output.run_id()
+    }
+
+    /// Everything recorded about the run.
+    pub fn into_output(self) -> WasmPackOutput {
+        self.output
+    }
+}
+
+impl Display for VersionOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.output.fmt(f)
+    }
+}
@@ -0,0 +1,295 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Rewrite `out_dir`, which is relative to the current directory, so that it's
+/// relative to `input_crate` instead, since that's how wasm-pack resolves it.
+///
+/// Both paths are normalized first, so `.` and paths that point back at the
+/// input crate come out as `.` rather than going up a directory. Absolute
+/// out-dirs are returned unchanged.
+pub(crate) fn out_dir_relative_to_input_crate(input_crate: &Path, out_dir: &Path) -> PathBuf {
+    if out_dir.is_absolute() {
+        return out_dir.to_path_buf();
+    }
+
+    let input_crate = normalize(input_crate);
+    let out_dir = normalize(out_dir);
+
+    // If the input crate is outside the current directory, we can't work out
+    // how to get back without knowing the current directory's name.
+    let input_crate_is_outside = input_crate.starts_with(Component::ParentDir.as_os_str());
+    if input_crate.is_absolute() || input_crate_is_outside {
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        return normalize(&current_dir.join(out_dir));
+    }
+
+    relative_path(&input_crate, &out_dir)
+}
+
+/// Work out the out-dir to pass to wasm-pack for `out_dir`, which is relative
+/// to `base`, which in turn is relative to the current directory unless it's
+/// absolute. An absolute `out_dir` ignores `base`.
+///
+/// The result is relative to `input_crate` whenever that's possible, even if
+/// `base` is absolute. It's only absolute when the two are on different
+/// drives or shares on Windows.
+pub(crate) fn out_dir_relative_to(input_crate: &Path, base: &Path, out_dir: &Path) -> PathBuf {
+    let out_dir = base.join(out_dir);
+    if out_dir.is_relative() {
+        return out_dir_relative_to_input_crate(input_crate, &out_dir);
+    }
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let input_crate = normalize(&current_dir.join(input_crate));
+    let out_dir = normalize(&out_dir);
+    let same_root = input_crate.components().next() == out_dir.components().next();
+    if !same_root {
+        return out_dir;
+    }
+    relative_path(&input_crate, &out_dir)
+}
+
+/// The path from `from` to `to`, which have to be normalized, and both
+/// relative or both absolute with the same root.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from_components = from.components().collect::<Vec<_>>();
+    let to_components = to.components().collect::<Vec<_>>();
+    let shared = from_components
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in shared..from_components.len() {
+        relative.push(Component::ParentDir);
+    }
+    for component in &to_components[shared..] {
+        relative.push(component);
+    }
+
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
+
+/// How long an absolute path can get before it needs the extended-length
+/// prefix on Windows. `MAX_PATH` is 260, and this leaves room for the names
+/// of the files wasm-pack writes inside an out-dir, like `snippets/...`.
+const LONG_PATH_THRESHOLD: usize = 200;
+
+/// The prefix that lifts Windows' `MAX_PATH` limit.
+const EXTENDED_LENGTH_PREFIX: &str = r"\\?\";
+
+/// On Windows, `path` as an absolute path with the `\\?\` prefix, if it's long
+/// enough to run into `MAX_PATH`. `None` if it's fine as it is, and always
+/// `None` elsewhere.
+///
+/// The Rust standard library does this for its own file operations, but
+/// wasm-pack and the tools it runs get paths as arguments, and fail with
+/// obscure IO errors on long ones.
+pub(crate) fn extended_length_path(path: &Path) -> Option<PathBuf> {
+    if !cfg!(windows) {
+        return None;
+    }
+
+    let absolute = normalize(&std::env::current_dir().ok()?.join(path));
+    let absolute = absolute.to_str()?;
+    if absolute.len() < LONG_PATH_THRESHOLD || absolute.starts_with(EXTENDED_LENGTH_PREFIX) {
+        return None;
+    }
+    Some(PathBuf::from(with_extended_length_prefix(absolute)))
+}
+
+/// Add the extended-length prefix to `path`, which has to be absolute and
+/// normalized, since Windows takes prefixed paths literally. That includes
+/// `/`, so separators become `\`.
+fn with_extended_length_prefix(path: &str) -> String {
+    if path.starts_with(EXTENDED_LENGTH_PREFIX) {
+        return path.to_string();
+    }
+
+    let path = path.replace('/', r"\");
+    match path.strip_prefix(r"\\") {
+        // UNC paths, like `\\server\share`, have their own form.
+        Some(unc_path) => format!(r"{}UNC\{}", EXTENDED_LENGTH_PREFIX, unc_path),
+        None => format!("{}{}", EXTENDED_LENGTH_PREFIX, path),
+    }
+}
+
+/// Remove `.` components and resolve `..` components where possible, without
+/// touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let can_pop = matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                );
+                if can_pop {
+                    normalized.pop();
+                } else if !normalized.has_root() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative(input_crate: &str, out_dir: &str) -> PathBuf {
+        out_dir_relative_to_input_crate(Path::new(input_crate), Path::new(out_dir))
+    }
+
+    #[test]
+    fn out_dir_is_the_input_crate() {
+        assert_eq!(relative(".", "."), Path::new("."));
+        assert_eq!(relative("test-crate", "test-crate"), Path::new("."));
+        assert_eq!(relative("./test-crate", "test-crate/"), Path::new("."));
+    }
+
+    #[test]
+    fn out_dir_is_inside_the_input_crate() {
+        assert_eq!(relative(".", "./pkg"), Path::new("pkg"));
+        assert_eq!(relative("test-crate", "test-crate/pkg"), Path::new("pkg"));
+        assert_eq!(
+            relative("test-crate", "./test-crate/./pkg"),
+            Path::new("pkg")
+        );
+    }
+
+    #[test]
+    fn out_dir_is_outside_the_input_crate() {
+        assert_eq!(
+            relative("test-crate", "target/built-test-crate"),
+            Path::new("../target/built-test-crate")
+        );
+        assert_eq!(
+            relative("crates/wasm", "target/pkg"),
+            Path::new("../../target/pkg")
+        );
+        assert_eq!(relative("test-crate", "../pkg"), Path::new("../../pkg"));
+    }
+
+    #[test]
+    fn absolute_out_dirs_are_unchanged() {
+        assert_eq!(relative("test-crate", "/tmp/pkg"), Path::new("/tmp/pkg"));
+    }
+
+    #[test]
+    fn out_dirs_relative_to_a_base() {
+        let relative_to = |input_crate: &str, base: &str, out_dir: &str| {
+            out_dir_relative_to(Path::new(input_crate), Path::new(base), Path::new(out_dir))
+        };
+
+        // The base is a sibling of the input crate.
+        assert_eq!(
+            relative_to("test-crate", "target", "pkg"),
+            Path::new("../target/pkg")
+        );
+        assert_eq!(
+            relative_to("crates/app", "crates/web", "pkg"),
+            Path::new("../web/pkg")
+        );
+
+        // The base is, or is inside, the input crate.
+        assert_eq!(
+            relative_to("test-crate", "test-crate", "pkg"),
+            Path::new("pkg")
+        );
+        assert_eq!(
+            relative_to("test-crate", "test-crate/target", "deep/pkg"),
+            Path::new("target/deep/pkg")
+        );
+        assert_eq!(relative_to("test-crate", ".", "test-crate"), Path::new("."));
+
+        // The base is above the input crate.
+        assert_eq!(
+            relative_to("crates/app", "..", "dist"),
+            Path::new("../../../dist")
+        );
+
+        // Absolute bases and out-dirs still give relative paths.
+        let current_dir = std::env::current_dir().unwrap();
+        let base = current_dir.join("target");
+        assert_eq!(
+            relative_to("test-crate", base.to_str().unwrap(), "pkg"),
+            Path::new("../target/pkg")
+        );
+        assert_eq!(
+            relative_to(
+                "test-crate",
+                "ignored",
+                base.join("./pkg").to_str().unwrap()
+            ),
+            Path::new("../target/pkg")
+        );
+        assert_eq!(
+            relative_to(
+                current_dir.join("test-crate").to_str().unwrap(),
+                "target",
+                "pkg"
+            ),
+            current_dir.join("target/pkg")
+        );
+        let mut up_to_root = PathBuf::new();
+        for _ in current_dir.join("test-crate").components().skip(1) {
+            up_to_root.push("..");
+        }
+        assert_eq!(
+            relative_to("test-crate", "/", "pkg"),
+            up_to_root.join("pkg")
+        );
+    }
+
+    #[test]
+    fn extended_length_prefixes() {
+        assert_eq!(
+            with_extended_length_prefix(r"C:\repo\target\pkg"),
+            r"\\?\C:\repo\target\pkg"
+        );
+        assert_eq!(
+            with_extended_length_prefix("C:/repo/target/pkg"),
+            r"\\?\C:\repo\target\pkg"
+        );
+        assert_eq!(
+            with_extended_length_prefix(r"\\server\share\pkg"),
+            r"\\?\UNC\server\share\pkg"
+        );
+        assert_eq!(
+            with_extended_length_prefix(r"\\?\C:\repo\pkg"),
+            r"\\?\C:\repo\pkg"
+        );
+
+        let deep = "nested/".repeat(40);
+        let extended = extended_length_path(Path::new(&deep));
+        if cfg!(windows) {
+            let extended = extended.unwrap();
+            assert!(extended
+                .to_str()
+                .unwrap()
+                .starts_with(EXTENDED_LENGTH_PREFIX));
+            assert!(extended.ends_with("nested"));
+        } else {
+            assert_eq!(extended, None);
+        }
+        assert_eq!(extended_length_path(Path::new("pkg")), None);
+    }
+
+    #[test]
+    fn input_crate_outside_the_current_directory() {
+        let expected = std::env::current_dir().unwrap().join("pkg");
+        assert_eq!(relative("../elsewhere", "pkg"), expected);
+    }
+}
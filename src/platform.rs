@@ -0,0 +1,285 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::WasmPackError;
+
+/// A platform there's an embedded wasm-pack executable for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    MacOs,
+
+    LinuxArm,
+    LinuxArm64,
+    LinuxX64,
+
+    Windows,
+}
+
+impl Platform {
+    /// Every platform, embedded or not.
+    pub(crate) const ALL: [Platform; 5] = [
+        Platform::MacOs,
+        Platform::LinuxArm,
+        Platform::LinuxArm64,
+        Platform::LinuxX64,
+        Platform::Windows,
+    ];
+
+    /// The platform this code is running on.
+    ///
+    /// - On macOS, this is always [`Platform::MacOs`]. Its executable is built
+    ///   for x86_64, and runs on Apple Silicon through Rosetta.
+    /// - On Linux, this is [`Platform::LinuxX64`] on x86_64,
+    ///   [`Platform::LinuxArm64`] on aarch64 and [`Platform::LinuxArm`] on
+    ///   32-bit ARM. wasm-pack doesn't publish an executable for 32-bit ARM,
+    ///   so there isn't an embedded one, but a custom executable can be run
+    ///   with [`WasmPack::executable`](crate::WasmPack::executable).
+    /// - On Windows, this is always [`Platform::Windows`].
+    ///
+    /// Anything else is a [`WasmPackError::UnsupportedPlatform`] error.
+    pub fn current() -> Result<Self, WasmPackError> {
+        Self::for_os_and_arch(std::env::consts::OS, std::env::consts::ARCH)
+    }
+
+    /// The cargo feature that bundles this platform's executable, if there's
+    /// one to bundle.
+    pub(crate) fn feature_name(&self) -> Option<&'static str> {
+        match self {
+            Platform::MacOs => Some("macos"),
+            Platform::LinuxArm => None,
+            Platform::LinuxArm64 => Some("linux-arm64"),
+            Platform::LinuxX64 => Some("linux-x64"),
+            Platform::Windows => Some("windows"),
+        }
+    }
+
+    /// The `std::env::consts::OS` and `ARCH` values this platform's
+    /// executable is built for, the inverse of `for_os_and_arch`.
+    fn os_and_arch(&self) -> (&'static str, &'static str) {
+        match self {
+            Platform::MacOs => ("macos", "x86_64"),
+            Platform::LinuxArm => ("linux", "arm"),
+            Platform::LinuxArm64 => ("linux", "aarch64"),
+            Platform::LinuxX64 => ("linux", "x86_64"),
+            Platform::Windows => ("windows", "x86_64"),
+        }
+    }
+
+    fn for_os_and_arch(os: &str, arch: &str) -> Result<Self, WasmPackError> {
+        let unsupported = || WasmPackError::UnsupportedPlatform {
+            os: os.to_string(),
+            arch: arch.to_string(),
+        };
+
+        match os {
+            "macos" => Ok(Platform::MacOs),
+            "linux" => match arch {
+                "x86_64" => Ok(Platform::LinuxX64),
+                "aarch64" => Ok(Platform::LinuxArm64),
+                "arm" => Ok(Platform::LinuxArm),
+                _ => Err(unsupported()),
+            },
+            "windows" => Ok(Platform::Windows),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Platform::MacOs => "x86_64-apple-darwin",
+            Platform::LinuxArm => "armv7-unknown-linux-musleabihf",
+            Platform::LinuxArm64 => "aarch64-unknown-linux-musl",
+            Platform::LinuxX64 => "x86_64-unknown-linux-musl",
+            Platform::Windows => "x86_64-pc-windows-msvc",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parses the target triples that [`Platform`]'s `Display` produces, like
+/// `"x86_64-apple-darwin"`.
+impl std::str::FromStr for Platform {
+    type Err = UnknownPlatform;
+
+    fn from_str(triple: &str) -> Result<Self, Self::Err> {
+        Platform::ALL
+            .into_iter()
+            .find(|platform| platform.to_string() == triple)
+            .ok_or_else(|| UnknownPlatform {
+                triple: triple.to_string(),
+            })
+    }
+}
+
+impl TryFrom<&str> for Platform {
+    type Error = UnknownPlatform;
+
+    fn try_from(triple: &str) -> Result<Self, Self::Error> {
+        triple.parse()
+    }
+}
+
+/// The error from parsing a [`Platform`] from a target triple that isn't one
+/// of its variants'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPlatform {
+    triple: String,
+}
+
+impl UnknownPlatform {
+    /// The target triple that couldn't be parsed.
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+}
+
+impl std::fmt::Display for UnknownPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} isn't a platform wasm-pack supports", self.triple)
+    }
+}
+
+impl std::error::Error for UnknownPlatform {}
+
+/// The embedded wasm-pack executable for `platform`, which doesn't have to be
+/// the platform this code is running on. Returns `None` if the platform's cargo
+/// feature is disabled.
+pub fn bundled_binary(platform: Platform) -> Option<&'static [u8]> {
+    match platform {
+        #[cfg(feature = "macos")]
+        Platform::MacOs => Some(include_bytes!(
+            "./wasm-pack-v0.12.1-x86_64-apple-darwin/wasm-pack"
+        )),
+        #[cfg(feature = "linux-arm64")]
+        Platform::LinuxArm64 => Some(include_bytes!(
+            "./wasm-pack-v0.12.1-aarch64-unknown-linux-musl/wasm-pack"
+        )),
+        #[cfg(feature = "linux-x64")]
+        Platform::LinuxX64 => Some(include_bytes!(
+            "./wasm-pack-v0.12.1-x86_64-unknown-linux-musl/wasm-pack"
+        )),
+        #[cfg(feature = "windows")]
+        Platform::Windows => Some(include_bytes!(
+            "./wasm-pack-v0.12.1-x86_64-pc-windows-msvc/wasm-pack.exe"
+        )),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Platforms serialize as their target triples.
+impl serde::Serialize for Platform {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The wasm-pack executable for `platform`. If it isn't embedded and the
+/// `download` feature is enabled, it's downloaded into `target_dir` instead.
+pub(crate) fn get_cli_executable_bytes(
+    platform: Platform,
+    #[cfg_attr(not(feature = "download"), allow(unused_variables))] target_dir: &Path,
+) -> Result<Cow<'static, [u8]>, WasmPackError> {
+    if let Some(bytes) = bundled_binary(platform) {
+        return Ok(Cow::Borrowed(bytes));
+    }
+
+    // There's no release to embed or download.
+    if platform.feature_name().is_none() {
+        let (os, arch) = platform.os_and_arch();
+        return Err(WasmPackError::UnsupportedPlatform {
+            os: os.to_string(),
+            arch: arch.to_string(),
+        });
+    }
+
+    #[cfg(feature = "download")]
+    {
+        crate::download::cached_or_downloaded(platform, target_dir)
+            .map(Cow::Owned)
+            .map_err(WasmPackError::CouldntDownloadWasmPack)
+    }
+
+    #[cfg(not(feature = "download"))]
+    Err(WasmPackError::PlatformNotBundled { platform })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detecting_platforms() {
+        let platform = |os, arch| Platform::for_os_and_arch(os, arch).ok();
+        assert_eq!(platform("macos", "aarch64"), Some(Platform::MacOs));
+        assert_eq!(platform("linux", "x86_64"), Some(Platform::LinuxX64));
+        assert_eq!(platform("linux", "aarch64"), Some(Platform::LinuxArm64));
+        assert_eq!(platform("linux", "arm"), Some(Platform::LinuxArm));
+        assert_eq!(platform("windows", "x86_64"), Some(Platform::Windows));
+
+        for platform in Platform::ALL {
+            let (os, arch) = platform.os_and_arch();
+            assert_eq!(Platform::for_os_and_arch(os, arch).ok(), Some(platform));
+        }
+    }
+
+    #[test]
+    fn parsing_platforms() {
+        use std::str::FromStr;
+
+        for platform in Platform::ALL {
+            assert_eq!(Platform::from_str(&platform.to_string()), Ok(platform));
+            assert_eq!(
+                Platform::try_from(platform.to_string().as_str()),
+                Ok(platform)
+            );
+        }
+
+        let error = Platform::from_str("wasm32-unknown-unknown").unwrap_err();
+        assert_eq!(error.triple(), "wasm32-unknown-unknown");
+        assert!(Platform::from_str("").is_err());
+    }
+
+    #[test]
+    fn bundled_binaries_are_available_for_other_platforms() {
+        for platform in [
+            Platform::MacOs,
+            Platform::LinuxArm64,
+            Platform::LinuxX64,
+            Platform::Windows,
+        ] {
+            let bytes = bundled_binary(platform).expect("Default features bundle every platform.");
+            assert!(!bytes.is_empty());
+        }
+
+        let windows_binary = bundled_binary(Platform::Windows).unwrap();
+        assert_eq!(&windows_binary[..2], b"MZ");
+    }
+
+    #[test]
+    fn linux_arm_has_no_executable() {
+        assert_eq!(bundled_binary(Platform::LinuxArm), None);
+        let result = get_cli_executable_bytes(Platform::LinuxArm, Path::new("target"));
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedPlatform { os, arch }) if os == "linux" && arch == "arm"
+        ));
+    }
+
+    #[test]
+    fn unsupported_platforms_are_errors() {
+        let result = Platform::for_os_and_arch("linux", "riscv64");
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedPlatform { os, arch }) if os == "linux" && arch == "riscv64"
+        ));
+
+        let result = Platform::for_os_and_arch("freebsd", "x86_64");
+        assert!(matches!(
+            result,
+            Err(WasmPackError::UnsupportedPlatform { os, .. }) if os == "freebsd"
+        ));
+    }
+}
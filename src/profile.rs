@@ -0,0 +1,109 @@
+use std::ffi::OsString;
+
+/// Which profile wasm-pack builds with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuildProfile {
+    /// `--dev`: debug info, no optimizations.
+    Dev,
+    /// `--release`: optimizations, no debug info. This is wasm-pack's default
+    /// for `build`.
+    Release,
+    /// `--profiling`: optimizations and debug info.
+    Profiling,
+}
+
+impl BuildProfile {
+    pub(crate) fn flag(&self) -> &'static str {
+        match self {
+            BuildProfile::Dev => "--dev",
+            BuildProfile::Release => "--release",
+            BuildProfile::Profiling => "--profiling",
+        }
+    }
+
+    /// The profile's name, like `"dev"`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            BuildProfile::Dev => "dev",
+            BuildProfile::Release => "release",
+            BuildProfile::Profiling => "profiling",
+        }
+    }
+
+    /// Work out which profile wasm-pack will use from its arguments, the same
+    /// way wasm-pack does. Returns `None` for subcommands that don't build.
+    pub(crate) fn from_args(args: &[OsString]) -> Option<Self> {
+        let mut args = args
+            .iter()
+            .map(|arg| arg.to_str().unwrap_or_default())
+            // Everything after `--` is for cargo.
+            .take_while(|arg| *arg != "--");
+
+        let mut subcommand = args.next()?;
+        while subcommand.starts_with('-') {
+            // `--log-level` is the only global flag that takes a value.
+            if subcommand == "--log-level" {
+                args.next();
+            }
+            subcommand = args.next()?;
+        }
+        let flags = args.filter(|arg| arg.starts_with('-'));
+
+        match subcommand {
+            "build" => Some(
+                flags.fold(BuildProfile::Release, |profile, flag| match flag {
+                    "--dev" | "--debug" => BuildProfile::Dev,
+                    "--release" => BuildProfile::Release,
+                    "--profiling" => BuildProfile::Profiling,
+                    _ => profile,
+                }),
+            ),
+            "test" => Some(flags.fold(BuildProfile::Dev, |profile, flag| match flag {
+                "--release" | "-r" => BuildProfile::Release,
+                _ => profile,
+            })),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_from(args: &[&str]) -> Option<BuildProfile> {
+        let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+        BuildProfile::from_args(&args)
+    }
+
+    #[test]
+    fn parsing_profiles_from_args() {
+        assert_eq!(
+            profile_from(&["build", "crate"]),
+            Some(BuildProfile::Release)
+        );
+        assert_eq!(
+            profile_from(&["build", "--dev", "crate"]),
+            Some(BuildProfile::Dev)
+        );
+        assert_eq!(
+            profile_from(&["--log-level", "warn", "-v", "build", "--dev", "crate"]),
+            Some(BuildProfile::Dev)
+        );
+        assert_eq!(
+            profile_from(&["build", "--profiling", "crate"]),
+            Some(BuildProfile::Profiling)
+        );
+        assert_eq!(
+            profile_from(&["build", "crate", "--", "--dev"]),
+            Some(BuildProfile::Release)
+        );
+        assert_eq!(profile_from(&["test", "--node"]), Some(BuildProfile::Dev));
+        assert_eq!(
+            profile_from(&["test", "-r", "--node"]),
+            Some(BuildProfile::Release)
+        );
+        assert_eq!(profile_from(&["--version"]), None);
+        assert_eq!(profile_from(&["pack", "crate"]), None);
+    }
+}
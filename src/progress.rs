@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use crate::lines::{LineCallback, OutputLine};
+
+/// Environment variables that make cargo draw its progress bar, with the
+/// `done/total` counts the progress is worked out from, even though its
+/// stderr isn't a terminal. Cargo needs a width to draw it at.
+pub(crate) const PROGRESS_BAR_ENV: [(&str, &str); 2] = [
+    ("CARGO_TERM_PROGRESS_WHEN", "always"),
+    ("CARGO_TERM_PROGRESS_WIDTH", "80"),
+];
+
+/// A function that's called with how far along the build is, from 0 to 1.
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(pub(crate) Arc<dyn Fn(f32) + Send + Sync>);
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Call `on_progress` whenever a line has progress counts, then pass the line
+/// on to `next` without cargo's progress bar.
+pub(crate) fn progress_callback(
+    on_progress: ProgressCallback,
+    next: Option<LineCallback>,
+) -> LineCallback {
+    LineCallback(Arc::new(move |line| {
+        if let Some(fraction) = parse_fraction(&line.text) {
+            (on_progress.0)(fraction);
+        }
+        if let Some(next) = &next {
+            let line = OutputLine {
+                stream: line.stream,
+                text: strip_progress_bars(&line.text),
+            };
+            (next.0)(&line);
+        }
+    }))
+}
+
+/// How far along the last count in `line` is, like `12/34` in cargo's
+/// "Building [=====>     ] 12/34: my_crate" progress bar, clamped to
+/// between 0 and 1. `None` if there's no count, or its total is 0.
+fn parse_fraction(line: &str) -> Option<f32> {
+    line.split('\r')
+        .map(str::trim_start)
+        .filter(|segment| segment.starts_with("Building") || segment.starts_with("Compiling"))
+        .flat_map(str::split_whitespace)
+        .filter_map(|word| {
+            let word = word.trim_matches(|c| matches!(c, '(' | ')' | ':'));
+            let (done, total) = word.split_once('/')?;
+            let done = done.parse::<u32>().ok()?;
+            let total = total.parse::<u32>().ok().filter(|&total| total > 0)?;
+            Some((done as f32 / total as f32).clamp(0.0, 1.0))
+        })
+        .next_back()
+}
+
+/// `output` without the progress bars cargo draws when forced to, so it
+/// reads as if cargo hadn't drawn them. Cargo redraws a bar by going back to
+/// the start of the line, so each line's text is what comes after the last
+/// `\r`.
+pub(crate) fn strip_progress_bars(output: &str) -> String {
+    output
+        .split_inclusive('\n')
+        .map(|line| {
+            let (line, ending) = match line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (line, ""),
+            };
+            let text = line.trim_end_matches('\r');
+            let text = text.rsplit('\r').next().unwrap_or_default();
+            format!("{}{}", text, ending)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputStream;
+    use std::sync::Mutex;
+
+    const BAR: &str = "    Building [=>          ] 1/4: proc-macro2(build.rs)   \r";
+
+    #[test]
+    fn parsing_progress() {
+        assert_eq!(parse_fraction(BAR), Some(0.25));
+        let line = format!(
+            "{}    Building [===>   ] 3/4: quote\r   Compiling syn v2.0.0",
+            BAR
+        );
+        assert_eq!(parse_fraction(&line), Some(0.75));
+        assert_eq!(parse_fraction("   Compiling my_crate (5/4)"), Some(1.0));
+        assert_eq!(
+            parse_fraction("   Compiling my_crate v0.1.0 (/src/a/b)"),
+            None
+        );
+        assert_eq!(parse_fraction("    Building [>   ] 0/0: my_crate"), None);
+        assert_eq!(parse_fraction("[INFO]: Compiling to Wasm..."), None);
+    }
+
+    #[test]
+    fn stripping_progress_bars() {
+        let output = format!(
+            "   Compiling a v1.0.0\n{}   Compiling b v1.0.0\r\n{}",
+            BAR, BAR
+        );
+        assert_eq!(
+            strip_progress_bars(&output),
+            "   Compiling a v1.0.0\n   Compiling b v1.0.0\n    Building [=>          ] 1/4: proc-macro2(build.rs)   "
+        );
+        assert_eq!(strip_progress_bars("no bars\n"), "no bars\n");
+    }
+
+    #[test]
+    fn passing_lines_on() {
+        let fractions = Arc::new(Mutex::new(Vec::new()));
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let on_progress = ProgressCallback(Arc::new({
+            let fractions = fractions.clone();
+            move |fraction| fractions.lock().unwrap().push(fraction)
+        }));
+        let next = LineCallback(Arc::new({
+            let lines = lines.clone();
+            move |line: &OutputLine| lines.lock().unwrap().push(line.text.clone())
+        }));
+        let callback = progress_callback(on_progress, Some(next));
+
+        for text in [
+            format!("{}   Compiling a v1.0.0", BAR),
+            "Finished".to_string(),
+        ] {
+            let line = OutputLine {
+                stream: OutputStream::Stderr,
+                text,
+            };
+            (callback.0)(&line);
+        }
+
+        assert_eq!(*fractions.lock().unwrap(), [0.25]);
+        assert_eq!(
+            *lines.lock().unwrap(),
+            ["   Compiling a v1.0.0", "Finished"]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Running wasm-pack from async code.
+//!
+//! wasm-pack runs on its own thread, like a blocking run would, and the future
+//! is woken when it finishes. That works with any executor, so the crate
+//! doesn't depend on a particular runtime.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::lines::LineCallback;
+use crate::{OutputLine, RunOptions, WasmPackError, WasmPackOutput};
+
+type RunResult = std::thread::Result<Result<WasmPackOutput, WasmPackError>>;
+
+/// Run wasm-pack with the given arguments without blocking, calling `on_line`
+/// with each line of its stdout and stderr as soon as it's printed.
+///
+/// wasm-pack starts straight away, on another thread, rather than when the
+/// returned future is first polled. `on_line` is called on the threads reading
+/// the output, so it can block without holding up the executor. Dropping the
+/// future before it finishes kills wasm-pack and deletes the extracted
+/// executable.
+///
+/// ```no_run
+/// # async fn build() -> Result<(), lib_wasm_pack::WasmPackError> {
+/// let output = lib_wasm_pack::run_async_with_callback(
+///     ["build", "--out-dir", "../target/built-test-crate", "test-crate"],
+///     |line| println!("{}", line.text),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn run_async_with_callback<Args>(
+    args: Args,
+    on_line: impl Fn(&OutputLine) + Send + Sync + 'static,
+) -> WasmPackFuture
+where
+    Args: IntoIterator,
+    Args::Item: Into<std::ffi::OsString>,
+{
+    let args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+    let options = RunOptions {
+        on_line: Some(LineCallback(Arc::new(on_line))),
+        ..RunOptions::default()
+    };
+    run_async_with_options(args, options)
+}
+
+pub(crate) fn run_async_with_options(
+    args: Vec<std::ffi::OsString>,
+    mut options: RunOptions,
+) -> WasmPackFuture {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            result: None,
+            waker: None,
+        }),
+        cancelled: Arc::new(AtomicBool::new(false)),
+    });
+    options.cancelled = Some(shared.cancelled.clone());
+
+    std::thread::spawn({
+        let shared = shared.clone();
+        move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                crate::run_with_options(args, &options)
+            }));
+
+            let mut state = shared.lock();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    });
+
+    WasmPackFuture {
+        shared,
+        finished: false,
+    }
+}
+
+/// The future returned by [`run_async_with_callback`]. Resolves to the same
+/// result as [`run`](crate::run).
+#[must_use = "wasm-pack is killed when the future is dropped"]
+#[derive(Debug)]
+pub struct WasmPackFuture {
+    shared: Arc<Shared>,
+    finished: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    result: Option<RunResult>,
+    waker: Option<Waker>,
+}
+
+impl Future for WasmPackFuture {
+    type Output = Result<WasmPackOutput, WasmPackError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.lock();
+        match state.result.take() {
+            Some(result) => {
+                drop(state);
+                self.finished = true;
+                match result {
+                    Ok(result) => Poll::Ready(result),
+                    Err(panic) => std::panic::resume_unwind(panic),
+                }
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for WasmPackFuture {
+    fn drop(&mut self) {
+        if !self.finished {
+            // The runner kills wasm-pack when it sees this, and the run cleans
+            // up on its thread as if it had been interrupted.
+            self.shared.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_wasm_pack;
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl std::task::Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn streaming_lines_while_awaiting() {
+        let dir = Path::new("target/run-async-test");
+        let script = fake_wasm_pack(dir, "#!/bin/sh\necho one\necho two >&2\necho three\n");
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let commands = Arc::new(Mutex::new(0));
+        let options = RunOptions {
+            executable: crate::Executable::Custom(script),
+            on_command: Some(crate::CommandCallback(Arc::new({
+                let commands = commands.clone();
+                move |_: &Path, _: &[std::ffi::OsString]| *commands.lock().unwrap() += 1
+            }))),
+            on_line: Some(LineCallback(Arc::new({
+                let lines = lines.clone();
+                move |line: &OutputLine| lines.lock().unwrap().push(line.text.clone())
+            }))),
+            ..RunOptions::default()
+        };
+
+        let output =
+            block_on(run_async_with_options(Vec::new(), options)).expect("Couldn't run script.");
+        assert_eq!(output.stdout(), "one\nthree");
+        let mut lines = lines.lock().unwrap().clone();
+        lines.sort();
+        assert_eq!(lines, ["one", "three", "two"]);
+        assert_eq!(*commands.lock().unwrap(), 1);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dropping_the_future_kills_wasm_pack() {
+        let dir = Path::new("target/run-async-drop-test");
+        let pid_file = dir.join("pid");
+        // The script runs in the current directory, like wasm-pack would.
+        let script = fake_wasm_pack(
+            dir,
+            &format!(
+                "#!/bin/sh\necho $$ > {}\nexec sleep 30\n",
+                pid_file.display()
+            ),
+        );
+
+        let options = RunOptions {
+            executable: crate::Executable::Custom(script),
+            ..RunOptions::default()
+        };
+        let future = run_async_with_options(Vec::new(), options);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let pid = loop {
+            let pid = std::fs::read_to_string(&pid_file).unwrap_or_default();
+            if let Ok(pid) = pid.trim().parse::<libc::pid_t>() {
+                break pid;
+            }
+            assert!(Instant::now() < deadline, "The script never started.");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        drop(future);
+
+        // SAFETY: Signal 0 only checks whether the process exists.
+        let is_running = || unsafe { libc::kill(pid, 0) } == 0;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while is_running() {
+            assert!(Instant::now() < deadline, "wasm-pack wasn't killed.");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+}
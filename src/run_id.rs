@@ -0,0 +1,72 @@
+//! Tagging log lines with the run they came from, so that concurrent runs'
+//! logs can be told apart.
+
+use std::cell::Cell;
+
+thread_local! {
+    static CURRENT_RUN: Cell<Option<uuid::Uuid>> = const { Cell::new(None) };
+}
+
+/// Makes `id` the current run on this thread until it's dropped.
+pub(crate) struct RunIdGuard {
+    previous: Option<uuid::Uuid>,
+}
+
+impl RunIdGuard {
+    pub(crate) fn enter(id: uuid::Uuid) -> Self {
+        let previous = CURRENT_RUN.with(|current| current.replace(Some(id)));
+        Self { previous }
+    }
+}
+
+impl Drop for RunIdGuard {
+    fn drop(&mut self) {
+        CURRENT_RUN.with(|current| current.set(self.previous));
+    }
+}
+
+pub(crate) fn current() -> Option<uuid::Uuid> {
+    CURRENT_RUN.with(Cell::get)
+}
+
+/// The short form of a run ID that's shown to people.
+pub(crate) fn short(id: uuid::Uuid) -> String {
+    id.simple().to_string()[..8].to_string()
+}
+
+/// `"[run 1a2b3c4d] "` during a run, and nothing otherwise.
+pub(crate) fn log_prefix() -> String {
+    current()
+        .map(|id| format!("[run {}] ", short(id)))
+        .unwrap_or_default()
+}
+
+/// Like the `log` macros, but with the current run ID at the start.
+macro_rules! run_log {
+    ($level:ident, $($arg:tt)+) => {
+        log::$level!("{}{}", $crate::run_id::log_prefix(), format_args!($($arg)+))
+    };
+}
+pub(crate) use run_log;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_ids_are_scoped() {
+        assert_eq!(log_prefix(), "");
+
+        let outer = uuid::Uuid::new_v4();
+        let guard = RunIdGuard::enter(outer);
+        assert_eq!(log_prefix(), format!("[run {}] ", short(outer)));
+        {
+            let _inner = RunIdGuard::enter(uuid::Uuid::new_v4());
+            assert_ne!(current(), Some(outer));
+        }
+        assert_eq!(current(), Some(outer));
+
+        drop(guard);
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,254 @@
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::lines::{LineCallback, LineTee};
+use crate::sink::{self, OutputSink};
+use crate::OutputStream;
+
+/// Runs the extracted wasm-pack executable. [`DuctRunner`] is used unless
+/// another runner is set with [`WasmPack::command_runner`](crate::WasmPack::command_runner).
+///
+/// Substituting a runner is mostly useful in tests, to return canned output
+/// without actually running wasm-pack:
+///
+/// ```
+/// use std::process::{ExitStatus, Output};
+///
+/// #[derive(Debug)]
+/// struct FakeRunner;
+///
+/// impl lib_wasm_pack::CommandRunner for FakeRunner {
+///     fn run(&self, _invocation: &lib_wasm_pack::Invocation) -> std::io::Result<Output> {
+///         Ok(Output {
+///             // `ExitStatus::default()` is a successful exit.
+///             status: ExitStatus::default(),
+///             stdout: b"wasm-pack 0.12.1".to_vec(),
+///             stderr: Vec::new(),
+///         })
+///     }
+/// }
+///
+/// let output = lib_wasm_pack::WasmPack::build("test-crate")
+///     .command_runner(FakeRunner)
+///     .run()
+///     .unwrap();
+/// assert_eq!(output.stdout(), "wasm-pack 0.12.1");
+/// ```
+pub trait CommandRunner: std::fmt::Debug + Send + Sync {
+    /// Run the invocation, capturing its output. A non-zero exit status should
+    /// be returned as part of the output, not as an error.
+    fn run(&self, invocation: &Invocation) -> io::Result<Output>;
+}
+
+/// Everything needed to run the wasm-pack executable once.
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    executable: PathBuf,
+    args: Vec<OsString>,
+    env: Vec<(OsString, OsString)>,
+    max_output_bytes: Option<usize>,
+    on_line: Option<LineCallback>,
+    stdin: Option<Vec<u8>>,
+    cancelled: Option<Arc<AtomicBool>>,
+    memory_limit: Option<u64>,
+    arg0: Option<OsString>,
+    stdout_sink: Option<Arc<dyn OutputSink>>,
+    stderr_sink: Option<Arc<dyn OutputSink>>,
+}
+
+impl Invocation {
+    pub(crate) fn new(executable: PathBuf, args: Vec<OsString>) -> Self {
+        Self {
+            executable,
+            args,
+            env: Vec::new(),
+            max_output_bytes: None,
+            on_line: None,
+            stdin: None,
+            cancelled: None,
+            memory_limit: None,
+            arg0: None,
+            stdout_sink: None,
+            stderr_sink: None,
+        }
+    }
+
+    pub(crate) fn sinks(
+        mut self,
+        stdout: Option<Arc<dyn OutputSink>>,
+        stderr: Option<Arc<dyn OutputSink>>,
+    ) -> Self {
+        self.stdout_sink = stdout;
+        self.stderr_sink = stderr;
+        self
+    }
+
+    pub(crate) fn memory_limit(mut self, bytes: Option<u64>) -> Self {
+        self.memory_limit = bytes;
+        self
+    }
+
+    pub(crate) fn arg0(mut self, arg0: Option<&OsStr>) -> Self {
+        self.arg0 = arg0.map(OsStr::to_os_string);
+        self
+    }
+
+    pub(crate) fn cancelled(mut self, cancelled: Option<Arc<AtomicBool>>) -> Self {
+        self.cancelled = cancelled;
+        self
+    }
+
+    pub(crate) fn stdin(mut self, bytes: Option<Vec<u8>>) -> Self {
+        self.stdin = bytes;
+        self
+    }
+
+    pub(crate) fn on_line(mut self, callback: Option<LineCallback>) -> Self {
+        self.on_line = callback;
+        self
+    }
+
+    pub(crate) fn max_output_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_output_bytes = limit;
+        self
+    }
+
+    pub(crate) fn env(mut self, name: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.env.push((name.into(), value.into()));
+        self
+    }
+
+    /// The path of the wasm-pack executable.
+    pub fn executable(&self) -> &Path {
+        &self.executable
+    }
+
+    /// The arguments to pass to wasm-pack.
+    pub fn args(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// Environment variables to set for wasm-pack, on top of the ones it
+    /// inherits.
+    pub fn env_vars(&self) -> impl Iterator<Item = (&OsStr, &OsStr)> {
+        self.env
+            .iter()
+            .map(|(name, value)| (name.as_os_str(), value.as_os_str()))
+    }
+
+    /// What to feed to wasm-pack's stdin. When this is `None`, runners should
+    /// give wasm-pack an empty stdin, so it can't wait for input.
+    pub fn stdin_bytes(&self) -> Option<&[u8]> {
+        self.stdin.as_deref()
+    }
+
+    /// Whether whoever started the run has stopped waiting for it, like when
+    /// the future from [`run_async_with_callback`](crate::run_async_with_callback)
+    /// is dropped. Runners should kill wasm-pack and return an
+    /// [`io::ErrorKind::Interrupted`] error when this becomes `true`.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::SeqCst))
+    }
+
+    /// How many bytes of memory wasm-pack and everything it runs may use,
+    /// from [`WasmPack::memory_limit`](crate::WasmPack::memory_limit).
+    /// Runners should enforce it where they can.
+    pub fn memory_limit_bytes(&self) -> Option<u64> {
+        self.memory_limit
+    }
+
+    /// What to start wasm-pack with as its `argv[0]`, so its messages don't
+    /// mention the path it was extracted to. Runners should set it where the
+    /// platform allows, and otherwise use the executable's path, which is
+    /// what `None` means too.
+    pub fn program_name(&self) -> Option<&OsStr> {
+        self.arg0.as_deref()
+    }
+
+    /// How many bytes of stdout and stderr to keep, each. Output past this is
+    /// truncated afterwards anyway, but runners can avoid buffering it.
+    pub fn output_limit(&self) -> Option<usize> {
+        self.max_output_bytes
+    }
+}
+
+/// The default [`CommandRunner`], which runs the executable with `duct`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DuctRunner;
+
+impl CommandRunner for DuctRunner {
+    fn run(&self, invocation: &Invocation) -> io::Result<Output> {
+        let mut expression = duct::cmd(invocation.executable(), invocation.args());
+        for (name, value) in invocation.env_vars() {
+            expression = expression.env(name, value);
+        }
+        expression = match invocation.stdin_bytes() {
+            Some(bytes) => expression.stdin_bytes(bytes),
+            None => expression.stdin_null(),
+        };
+        if let Some(bytes) = invocation.memory_limit_bytes() {
+            expression = crate::memory_limit::apply(expression, bytes);
+        }
+        if let Some(arg0) = invocation.program_name() {
+            expression = crate::arg0::apply(expression, arg0);
+        }
+
+        // We read the output ourselves rather than having duct capture it, so
+        // that it can be truncated as it's read.
+        let (stdout_reader, stdout_writer) = os_pipe::pipe()?;
+        let (stderr_reader, stderr_writer) = os_pipe::pipe()?;
+
+        let interrupts = crate::interrupt::interrupts_received();
+        let handle = expression
+            .stdout_file(stdout_writer)
+            .stderr_file(stderr_writer)
+            .unchecked()
+            .start()?;
+        // Our copies of the writers were dropped with the expression, so the
+        // readers see the end of the output when wasm-pack exits.
+
+        let read = |reader: os_pipe::PipeReader, stream| {
+            let limit = invocation.output_limit();
+            let on_line = invocation.on_line.clone();
+            let sink = match stream {
+                OutputStream::Stdout => invocation.stdout_sink.clone(),
+                OutputStream::Stderr => invocation.stderr_sink.clone(),
+            };
+            std::thread::spawn(move || match on_line {
+                Some(callback) => {
+                    let reader = LineTee::new(reader, stream, callback);
+                    sink::drain(reader, sink, limit)
+                }
+                None => sink::drain(reader, sink, limit),
+            })
+        };
+        let stdout = read(stdout_reader, OutputStream::Stdout);
+        let stderr = read(stderr_reader, OutputStream::Stderr);
+
+        while handle.try_wait()?.is_none() {
+            if crate::interrupt::interrupts_received() != interrupts || invocation.is_cancelled() {
+                handle.kill()?;
+                let message = "wasm-pack was interrupted";
+                return Err(io::Error::new(io::ErrorKind::Interrupted, message));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let status = handle.into_output()?.status;
+        let join = |reader: std::thread::JoinHandle<io::Result<Vec<u8>>>| {
+            reader.join().expect("Output reader thread panicked.")
+        };
+
+        Ok(Output {
+            status,
+            stdout: join(stdout)?,
+            stderr: join(stderr)?,
+        })
+    }
+}
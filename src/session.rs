@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    BuildOutput, BuildProfile, Executable, RunOptions, Target, WasmPack, WasmPackError,
+    WasmPackOutput,
+};
+
+type ProgressCallback = Box<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Runs several wasm-pack invocations with a single extracted executable,
+/// which is deleted when the session is dropped.
+///
+/// ```
+/// let session = lib_wasm_pack::WasmPackSession::new()
+///     .expect("Couldn't extract wasm-pack.")
+///     .on_progress(|done, total| println!("{}/{} wasm-pack runs finished.", done, total));
+///
+/// let results = session.run_all(vec![vec!["--version"], vec!["--help"]]);
+/// assert!(results.iter().all(Result::is_ok));
+/// ```
+pub struct WasmPackSession {
+    executable: PathBuf,
+    on_progress: Option<ProgressCallback>,
+    fail_fast: bool,
+}
+
+impl WasmPackSession {
+    /// Extract the wasm-pack executable for the session.
+    pub fn new() -> Result<Self, WasmPackError> {
+        let (executable, _) = crate::get_cli_executable_file(&RunOptions::default())?;
+
+        Ok(Self {
+            executable,
+            on_progress: None,
+            fail_fast: false,
+        })
+    }
+
+    /// Call `on_progress(done, total)` each time a run in
+    /// [`run_all`](Self::run_all) finishes.
+    pub fn on_progress(
+        mut self,
+        on_progress: impl Fn(usize, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Stop [`run_all`](Self::run_all) at the first failed run, instead of
+    /// carrying on with the rest. Disabled by default.
+    pub fn fail_fast(mut self, enabled: bool) -> Self {
+        self.fail_fast = enabled;
+        self
+    }
+
+    /// Carry on with the rest of the runs after one fails. This is the
+    /// default, and undoes [`fail_fast`](Self::fail_fast). Use
+    /// [`run_all_summary`](Self::run_all_summary) to see which runs failed.
+    pub fn continue_on_error(self) -> Self {
+        self.fail_fast(false)
+    }
+
+    /// The path of the extracted executable.
+    pub fn executable(&self) -> &Path {
+        &self.executable
+    }
+
+    /// Run wasm-pack with the given arguments.
+    pub fn run<Args>(&self, args: Args) -> Result<WasmPackOutput, WasmPackError>
+    where
+        Args: IntoIterator,
+        Args::Item: Into<OsString>,
+    {
+        let args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+        crate::run_with_options(args, &self.options())
+    }
+
+    /// Run wasm-pack once for each set of arguments, in order.
+    ///
+    /// Returns a result per run. With [`fail_fast`](Self::fail_fast), the
+    /// results stop at the first error.
+    pub fn run_all<Jobs, Args>(&self, jobs: Jobs) -> Vec<Result<WasmPackOutput, WasmPackError>>
+    where
+        Jobs: IntoIterator<Item = Args>,
+        Args: IntoIterator,
+        Args::Item: Into<OsString>,
+    {
+        self.run_jobs(jobs)
+            .into_iter()
+            .map(|(_args, result)| result)
+            .collect()
+    }
+
+    /// Like [`run_all`](Self::run_all), but sorts the runs into the ones that
+    /// succeeded and the ones that failed, along with their arguments, so
+    /// they can be told apart.
+    pub fn run_all_summary<Jobs, Args>(&self, jobs: Jobs) -> RunSummary
+    where
+        Jobs: IntoIterator<Item = Args>,
+        Args: IntoIterator,
+        Args::Item: Into<OsString>,
+    {
+        let results = self.run_jobs(jobs);
+        let mut summary = RunSummary {
+            total: results.len(),
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        };
+
+        for (index, (args, result)) in results.into_iter().enumerate() {
+            match result {
+                Ok(output) => summary.succeeded.push(FinishedRun {
+                    index,
+                    args,
+                    outcome: output,
+                }),
+                Err(error) => summary.failed.push(FinishedRun {
+                    index,
+                    args,
+                    outcome: error,
+                }),
+            }
+        }
+
+        summary
+    }
+
+    fn run_jobs<Jobs, Args>(
+        &self,
+        jobs: Jobs,
+    ) -> Vec<(Vec<OsString>, Result<WasmPackOutput, WasmPackError>)>
+    where
+        Jobs: IntoIterator<Item = Args>,
+        Args: IntoIterator,
+        Args::Item: Into<OsString>,
+    {
+        let jobs = jobs.into_iter().collect::<Vec<_>>();
+        let total = jobs.len();
+        let mut results = Vec::with_capacity(total);
+
+        for (index, args) in jobs.into_iter().enumerate() {
+            let args = args.into_iter().map(Into::into).collect::<Vec<_>>();
+            let result = self.run(args.clone());
+            let failed = result.is_err();
+            results.push((args, result));
+
+            if let Some(on_progress) = &self.on_progress {
+                on_progress(index + 1, total);
+            }
+
+            if failed && self.fail_fast {
+                break;
+            }
+        }
+
+        results
+    }
+
+    pub(crate) fn options(&self) -> RunOptions {
+        RunOptions {
+            executable: Executable::Extracted(self.executable.clone()),
+            ..RunOptions::default()
+        }
+    }
+}
+
+/// Build `input_crate` once with each of `profiles`, into a subdirectory of
+/// `out_dir_base` named after the profile, like `dev` or `release`, and return
+/// each profile's output. Compare their sizes with
+/// [`BuildOutput::wasm_size`].
+///
+/// `out_dir_base` is relative to the current directory, like the input crate
+/// path. The executable is only extracted once, for all the builds. Stops at
+/// the first build that fails.
+pub fn build_profiles(
+    input_crate: impl Into<PathBuf>,
+    out_dir_base: impl AsRef<Path>,
+    profiles: &[BuildProfile],
+) -> Result<Vec<(BuildProfile, BuildOutput)>, WasmPackError> {
+    let input_crate = input_crate.into();
+    let session = WasmPackSession::new()?;
+
+    profiles
+        .iter()
+        .map(|&profile| {
+            let output = WasmPack::build(&input_crate)
+                .extracted_executable(session.executable())
+                .profile(profile)
+                .out_dir_from_current_dir(out_dir_base.as_ref().join(profile.name()))
+                .run()?;
+            Ok((profile, output))
+        })
+        .collect()
+}
+
+/// Build `input_crate` once for each of `targets`, into a subdirectory of
+/// `out_dir_base` named after the target's `--target` value, like `web` or
+/// `nodejs`, and return each target's output. Every build names its files
+/// after the crate, so they only differ in their directory.
+///
+/// `out_dir_base` is relative to the current directory, like the input crate
+/// path. The executable is only extracted once, for all the builds. Stops at
+/// the first build that fails.
+pub fn build_targets(
+    input_crate: impl Into<PathBuf>,
+    out_dir_base: impl AsRef<Path>,
+    targets: &[Target],
+) -> Result<HashMap<Target, BuildOutput>, WasmPackError> {
+    build_targets_with(
+        input_crate.into(),
+        out_dir_base.as_ref(),
+        targets,
+        |build| build,
+    )
+}
+
+fn build_targets_with(
+    input_crate: PathBuf,
+    out_dir_base: &Path,
+    targets: &[Target],
+    configure: impl Fn(WasmPack) -> WasmPack,
+) -> Result<HashMap<Target, BuildOutput>, WasmPackError> {
+    let session = WasmPackSession::new()?;
+
+    targets
+        .iter()
+        .map(|&target| {
+            let build = WasmPack::build(&input_crate)
+                .extracted_executable(session.executable())
+                .target(target)
+                .out_dir_from_current_dir(out_dir_base.join(target.as_str()));
+            let output = configure(build).run()?;
+            Ok((target, output))
+        })
+        .collect()
+}
+
+/// One run from [`WasmPackSession::run_all_summary`], with its output or
+/// error as `outcome`.
+#[derive(Debug)]
+pub struct FinishedRun<T> {
+    /// Where the run was in the list passed to `run_all_summary`.
+    pub index: usize,
+    pub args: Vec<OsString>,
+    pub outcome: T,
+}
+
+/// Which runs from [`WasmPackSession::run_all_summary`] succeeded and which
+/// failed, each in the order they ran.
+#[derive(Debug)]
+pub struct RunSummary {
+    pub total: usize,
+    pub succeeded: Vec<FinishedRun<WasmPackOutput>>,
+    pub failed: Vec<FinishedRun<WasmPackError>>,
+}
+
+impl RunSummary {
+    /// The outputs of every run if they all succeeded, or a
+    /// [`RunAllError`] listing the ones that failed.
+    pub fn into_result(self) -> Result<Vec<WasmPackOutput>, RunAllError> {
+        if !self.failed.is_empty() {
+            return Err(RunAllError {
+                total: self.total,
+                failed: self.failed,
+            });
+        }
+
+        Ok(self.succeeded.into_iter().map(|run| run.outcome).collect())
+    }
+}
+
+/// Some of the runs from [`WasmPackSession::run_all_summary`] failed.
+#[derive(Debug)]
+pub struct RunAllError {
+    pub total: usize,
+    pub failed: Vec<FinishedRun<WasmPackError>>,
+}
+
+impl std::fmt::Display for RunAllError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} of {} wasm-pack runs failed:",
+            self.failed.len(),
+            self.total
+        )?;
+        for run in &self.failed {
+            writeln!(f)?;
+            writeln!(f, "Run {} ({:?}): {}", run.index + 1, run.args, run.outcome)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RunAllError {}
+
+impl std::fmt::Debug for WasmPackSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPackSession")
+            .field("executable", &self.executable)
+            .field("fail_fast", &self.fail_fast)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for WasmPackSession {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_file(&self.executable) {
+            log::warn!(
+                "Couldn't delete temporary file {:?}: {}",
+                self.executable,
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn jobs() -> Vec<Vec<&'static str>> {
+        vec![
+            vec!["--version"],
+            vec!["build", "fake-crate"],
+            vec!["--version"],
+        ]
+    }
+
+    #[test]
+    fn building_several_profiles() {
+        let out_dir_base = Path::new("target/built-test-crate-profiles");
+        let outputs = build_profiles("test-crate", out_dir_base, &[BuildProfile::Dev])
+            .expect("Couldn't build profiles.");
+
+        assert_eq!(outputs.len(), 1);
+        let (profile, output) = &outputs[0];
+        assert_eq!(*profile, BuildProfile::Dev);
+        assert_eq!(output.profile(), BuildProfile::Dev);
+        assert!(output.wasm_size() > 0);
+        assert!(out_dir_base.join("dev").join("test_crate_bg.wasm").exists());
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir_base);
+    }
+
+    #[test]
+    fn building_several_targets() {
+        let out_dir_base = Path::new("target/built-test-crate-targets");
+        let outputs = build_targets_with(
+            "test-crate".into(),
+            out_dir_base,
+            &[Target::Web, Target::NodeJs],
+            |build| build.profile(BuildProfile::Dev),
+        )
+        .expect("Couldn't build targets.");
+
+        assert_eq!(outputs.len(), 2);
+        for (target, directory) in [(Target::Web, "web"), (Target::NodeJs, "nodejs")] {
+            let output = &outputs[&target];
+            assert!(output.out_dir().ends_with(out_dir_base.join(directory)));
+            assert_eq!(output.output_name().as_deref(), Some("test_crate"));
+            assert!(out_dir_base
+                .join(directory)
+                .join("test_crate_bg.wasm")
+                .exists());
+        }
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir_base);
+    }
+
+    #[test]
+    fn running_all_jobs_reports_progress() {
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let progress_clone = progress.clone();
+        let session = WasmPackSession::new()
+            .unwrap()
+            .on_progress(move |done, total| progress_clone.lock().unwrap().push((done, total)));
+
+        let results = session.run_all(jobs());
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        assert_eq!(*progress.lock().unwrap(), vec![(1, 3), (2, 3), (3, 3)]);
+
+        let executable = session.executable().to_path_buf();
+        assert!(executable.exists());
+        drop(session);
+        assert!(!executable.exists());
+    }
+
+    #[test]
+    fn summarizing_runs() {
+        let session = WasmPackSession::new()
+            .unwrap()
+            .fail_fast(true)
+            .continue_on_error();
+        let summary = session.run_all_summary(jobs());
+
+        assert_eq!(summary.total, 3);
+        let succeeded = summary.succeeded.iter().map(|run| run.index);
+        assert_eq!(succeeded.collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].args, vec!["build", "fake-crate"]);
+
+        let error = summary.into_result().unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("1 of 3 wasm-pack runs failed:\n\nRun 2 ([\"build\", \"fake-crate\"]): "));
+    }
+
+    #[test]
+    fn failing_fast() {
+        let session = WasmPackSession::new().unwrap().fail_fast(true);
+        let results = session.run_all(jobs());
+        assert_eq!(results.len(), 2);
+        assert!(results[1].is_err());
+    }
+}
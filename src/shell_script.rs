@@ -0,0 +1,61 @@
+use std::ffi::OsString;
+
+/// A POSIX `sh` script that exports `env` and runs `program` with `args`.
+pub(crate) fn render(program: &str, args: &[OsString], env: &[(OsString, OsString)]) -> String {
+    let mut script = String::from("#!/bin/sh\nset -e\n");
+    for (name, value) in env {
+        script.push_str(&format!(
+            "export {}={}\n",
+            name.to_string_lossy(),
+            quote(&value.to_string_lossy())
+        ));
+    }
+
+    script.push_str(program);
+    for arg in args {
+        script.push(' ');
+        script.push_str(&quote(&arg.to_string_lossy()));
+    }
+    script.push('\n');
+    script
+}
+
+/// Quote `word` for a POSIX shell, leaving it alone if nothing in it is
+/// special.
+pub(crate) fn quote(word: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-+=/.,:@%".contains(c);
+    if !word.is_empty() && word.chars().all(is_safe) {
+        return word.to_string();
+    }
+
+    // Nothing is special inside single quotes, so only single quotes need
+    // escaping, by closing the quotes around an escaped one.
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("--out-dir"), "--out-dir");
+        assert_eq!(quote("../target/pkg"), "../target/pkg");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("my crate"), "'my crate'");
+        assert_eq!(quote("it's $HOME"), r"'it'\''s $HOME'");
+    }
+
+    #[test]
+    fn rendering_a_script() {
+        let script = render(
+            "wasm-pack",
+            &["build".into(), "my crate".into()],
+            &[("CARGO_BUILD_JOBS".into(), "2".into())],
+        );
+        assert_eq!(
+            script,
+            "#!/bin/sh\nset -e\nexport CARGO_BUILD_JOBS=2\nwasm-pack build 'my crate'\n"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Where wasm-pack's stdout and stderr go as they're read, set per stream
+//! with [`WasmPack::output_sink`](crate::WasmPack::output_sink).
+//!
+//! By default both streams are captured in memory, into the run's output.
+//! The sinks here can be combined with [`Tee`], and anything else can
+//! implement [`OutputSink`].
+
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Somewhere to send one of wasm-pack's output streams. Sinks are shared by
+/// every run of a builder, so they take `&self`.
+pub trait OutputSink: std::fmt::Debug + Send + Sync {
+    /// Called with each chunk of output as soon as it's read. Chunks aren't
+    /// split on line boundaries.
+    fn write(&self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Whether the stream should also be captured into the run's output, like
+    /// [`WasmPackOutput::stdout`](crate::WasmPackOutput::stdout). Only
+    /// [`InMemory`] does this by default.
+    fn captures(&self) -> bool {
+        false
+    }
+}
+
+/// Capture the stream into the run's output, which is what happens when no
+/// sink is set. Combine it with other sinks using [`Tee`] to keep the output
+/// while also sending it elsewhere.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InMemory;
+
+impl OutputSink for InMemory {
+    fn write(&self, _bytes: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn captures(&self) -> bool {
+        true
+    }
+}
+
+/// Write the stream to a file. Every run appends to the same file, so
+/// sharing one between stdout and stderr interleaves them.
+#[derive(Debug)]
+pub struct File(Mutex<std::fs::File>);
+
+impl File {
+    /// Create the file at `path`, truncating it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::File::create(path).map(Self::from)
+    }
+}
+
+impl From<std::fs::File> for File {
+    fn from(file: std::fs::File) -> Self {
+        Self(Mutex::new(file))
+    }
+}
+
+impl OutputSink for File {
+    fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        let mut file = self.0.lock().unwrap_or_else(|error| error.into_inner());
+        file.write_all(bytes)
+    }
+}
+
+/// Write the stream to this process's stdout, as soon as it's read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stdout;
+
+impl OutputSink for Stdout {
+    fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(bytes)?;
+        stdout.flush()
+    }
+}
+
+/// Write the stream to this process's stderr, as soon as it's read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stderr;
+
+impl OutputSink for Stderr {
+    fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        let mut stderr = io::stderr().lock();
+        stderr.write_all(bytes)?;
+        stderr.flush()
+    }
+}
+
+type ChunkFn = dyn Fn(&[u8]) + Send + Sync;
+
+/// Call a function with each chunk of the stream. For whole lines, use
+/// [`WasmPack::on_output_line`](crate::WasmPack::on_output_line) instead.
+#[derive(Clone)]
+pub struct Callback(Arc<ChunkFn>);
+
+impl Callback {
+    pub fn new(callback: impl Fn(&[u8]) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl std::fmt::Debug for Callback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+impl OutputSink for Callback {
+    fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        (self.0)(bytes);
+        Ok(())
+    }
+}
+
+/// Send the stream to two sinks. It's captured if either of them captures
+/// it, and nest tees to send it to more.
+#[derive(Debug, Clone)]
+pub struct Tee(Arc<dyn OutputSink>, Arc<dyn OutputSink>);
+
+impl Tee {
+    pub fn new(first: impl OutputSink + 'static, second: impl OutputSink + 'static) -> Self {
+        Self(Arc::new(first), Arc::new(second))
+    }
+}
+
+impl OutputSink for Tee {
+    fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        // Give the second sink the output even if the first one failed.
+        let first = self.0.write(bytes);
+        let second = self.1.write(bytes);
+        first.and(second)
+    }
+
+    fn captures(&self) -> bool {
+        self.0.captures() || self.1.captures()
+    }
+}
+
+/// Passes bytes through from `reader`, writing them to `sink` as well.
+///
+/// If the sink fails, it's skipped for the rest of the output, and the error
+/// is returned once `reader` is done, so wasm-pack is never left blocked on
+/// a full pipe.
+pub(crate) struct SinkReader<R> {
+    reader: R,
+    sink: Arc<dyn OutputSink>,
+    error: Option<io::Error>,
+}
+
+impl<R> SinkReader<R> {
+    pub(crate) fn new(reader: R, sink: Arc<dyn OutputSink>) -> Self {
+        Self {
+            reader,
+            sink,
+            error: None,
+        }
+    }
+}
+
+impl<R: Read> Read for SinkReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        if read == 0 {
+            return match self.error.take() {
+                Some(error) => Err(error),
+                None => Ok(0),
+            };
+        }
+
+        if self.error.is_none() {
+            if let Err(error) = self.sink.write(&buf[..read]) {
+                self.error = Some(error);
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Read `reader` to the end through `sink`, returning what should be captured:
+/// all of it, up to `limit`, if the sink captures, and nothing otherwise.
+pub(crate) fn drain(
+    reader: impl Read,
+    sink: Option<Arc<dyn OutputSink>>,
+    limit: Option<usize>,
+) -> io::Result<Vec<u8>> {
+    let Some(sink) = sink else {
+        return crate::capture::read_capped(reader, limit);
+    };
+
+    let captures = sink.captures();
+    let mut reader = SinkReader::new(reader, sink);
+    if captures {
+        crate::capture::read_capped(reader, limit)
+    } else {
+        io::copy(&mut reader, &mut io::sink())?;
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct FailingSink;
+
+    impl OutputSink for FailingSink {
+        fn write(&self, _bytes: &[u8]) -> io::Result<()> {
+            Err(io::Error::other("disk full"))
+        }
+    }
+
+    #[test]
+    fn draining_into_sinks() {
+        let input = &b"compiling\nfinished\n"[..];
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let callback = Callback::new({
+            let received = received.clone();
+            move |bytes| received.lock().unwrap().extend_from_slice(bytes)
+        });
+
+        assert_eq!(drain(input, None, None).unwrap(), input);
+        assert_eq!(
+            drain(input, Some(Arc::new(callback.clone())), None).unwrap(),
+            b""
+        );
+        assert_eq!(*received.lock().unwrap(), input);
+
+        let tee = Tee::new(InMemory, callback);
+        assert!(tee.captures());
+        assert_eq!(drain(input, Some(Arc::new(tee)), None).unwrap(), input);
+        assert_eq!(received.lock().unwrap().len(), input.len() * 2);
+
+        let tee = Tee::new(FailingSink, InMemory);
+        let error = drain(input, Some(Arc::new(tee)), None).unwrap_err();
+        assert_eq!(error.to_string(), "disk full");
+    }
+}
@@ -0,0 +1,81 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Every file under `out_dir`, including in subdirectories like `snippets`,
+/// with its size in bytes. Sorted from largest to smallest, then by path.
+pub(crate) fn file_sizes(out_dir: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let mut sizes = Vec::new();
+    let mut dirs = vec![out_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                sizes.push((entry.path(), metadata.len()));
+            }
+        }
+    }
+
+    sizes.sort_by(|(path_a, size_a), (path_b, size_b)| {
+        size_b.cmp(size_a).then_with(|| path_a.cmp(path_b))
+    });
+    Ok(sizes)
+}
+
+/// Like [`file_sizes`], with each file's size after gzipping it at `level`
+/// too. Nothing is written to disk.
+pub(crate) fn gzipped_file_sizes(
+    out_dir: &Path,
+    level: u32,
+) -> io::Result<Vec<(PathBuf, u64, u64)>> {
+    // Checked up front, so an empty out-dir doesn't hide a bad level.
+    crate::gzip::check_level(level)?;
+    file_sizes(out_dir)?
+        .into_iter()
+        .map(|(path, size)| {
+            let compressed = crate::gzip::compress(&std::fs::read(&path)?, level)?;
+            Ok((path, size, compressed.len() as u64))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reporting_file_sizes() {
+        let out_dir = Path::new("target/size-report-test");
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+        std::fs::create_dir_all(out_dir.join("snippets")).unwrap();
+
+        std::fs::write(out_dir.join("crate_bg.wasm"), vec![0u8; 4096]).unwrap();
+        std::fs::write(out_dir.join("crate.js"), "export {};").unwrap();
+        std::fs::write(out_dir.join("package.json"), "{}").unwrap();
+        std::fs::write(out_dir.join("snippets").join("a.js"), "{}").unwrap();
+
+        let sizes = file_sizes(out_dir).unwrap();
+        assert_eq!(
+            sizes,
+            vec![
+                (out_dir.join("crate_bg.wasm"), 4096),
+                (out_dir.join("crate.js"), 10),
+                (out_dir.join("package.json"), 2),
+                (out_dir.join("snippets").join("a.js"), 2),
+            ]
+        );
+
+        let gzipped = gzipped_file_sizes(out_dir, 9).unwrap();
+        assert_eq!(gzipped.len(), 4);
+        let (path, size, gzipped_size) = &gzipped[0];
+        assert_eq!((path, *size), (&out_dir.join("crate_bg.wasm"), 4096));
+        assert!(*gzipped_size < 4096);
+
+        let error = gzipped_file_sizes(out_dir, 10).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+    }
+}
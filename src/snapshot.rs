@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// When each file in an out-dir was last modified, taken before a build, to
+/// tell which files the build wrote afterwards.
+#[derive(Debug, Default)]
+pub(crate) struct OutDirSnapshot {
+    taken_at: Option<SystemTime>,
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl OutDirSnapshot {
+    /// Record the files in `out_dir`. A missing out-dir has no files.
+    pub(crate) fn take(out_dir: &Path) -> io::Result<Self> {
+        let taken_at = Some(SystemTime::now());
+        let entries = match std::fs::read_dir(out_dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    taken_at,
+                    modified: HashMap::new(),
+                })
+            }
+            Err(error) => return Err(error),
+        };
+
+        let mut modified = HashMap::new();
+        for entry in entries {
+            let entry = entry?;
+            modified.insert(entry.path(), entry.metadata()?.modified().ok());
+        }
+
+        Ok(Self { taken_at, modified })
+    }
+
+    /// Whether the file at `path`, last modified at `modified`, was written
+    /// since the snapshot was taken.
+    ///
+    /// New files were, and so were files whose modification time changed.
+    /// Files modified since the snapshot was taken count too, in case the file
+    /// system's timestamps are too coarse to see the change. Without
+    /// modification times, every file counts.
+    pub(crate) fn was_written(&self, path: &Path, modified: Option<SystemTime>) -> bool {
+        let Some(before) = self.modified.get(path) else {
+            return true;
+        };
+
+        match (modified, self.taken_at) {
+            (Some(modified), Some(taken_at)) => {
+                *before != Some(modified) || modified >= truncate_to_seconds(taken_at)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Round `time` down to a whole second, the coarsest resolution file systems
+/// commonly have for modification times.
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since_epoch) => {
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(since_epoch.as_secs())
+        }
+        Err(_) => time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn telling_which_files_were_written() {
+        let out_dir = Path::new("target/snapshot-test");
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+        std::fs::create_dir_all(out_dir).unwrap();
+
+        let stale = out_dir.join("other_crate_bg.wasm");
+        let rewritten = out_dir.join("test_crate_bg.wasm");
+        std::fs::write(&stale, b"").unwrap();
+        std::fs::write(&rewritten, b"").unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(60 * 60);
+        for path in [&stale, &rewritten] {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(long_ago)
+                .unwrap();
+        }
+
+        let snapshot = OutDirSnapshot::take(out_dir).unwrap();
+        std::fs::write(&rewritten, b"new").unwrap();
+        let new = out_dir.join("test_crate.js");
+        std::fs::write(&new, b"").unwrap();
+
+        let was_written = |path: &Path| {
+            let modified = std::fs::metadata(path).unwrap().modified().ok();
+            snapshot.was_written(path, modified)
+        };
+        assert!(!was_written(&stale));
+        assert!(was_written(&rewritten));
+        assert!(was_written(&new));
+
+        let snapshot = OutDirSnapshot::take(&out_dir.join("missing")).unwrap();
+        assert!(snapshot.was_written(&new, None));
+
+        let _ignore_errors = std::fs::remove_dir_all(out_dir);
+    }
+}
@@ -0,0 +1,149 @@
+//! Markers for the wasm-pack subcommands the [`WasmPack`](crate::WasmPack)
+//! builder can run. Each one decides which builder methods are available, and
+//! what running returns.
+
+use crate::{BuildOutput, PackOutput, TestOutput, VersionOutput, WasmPackOutput};
+
+/// A wasm-pack subcommand. Implemented by the markers in this module, and
+/// sealed so it can't be implemented anywhere else.
+pub trait Subcommand: private::Sealed + std::fmt::Debug + Clone {
+    /// What running the subcommand returns.
+    type Output;
+}
+
+/// A subcommand that runs on a crate: everything except [`Version`] and
+/// [`Login`].
+pub trait CrateSubcommand: Subcommand {}
+
+/// A subcommand that compiles the crate with cargo: [`Build`] and [`Test`].
+pub trait CompilingSubcommand: CrateSubcommand {}
+
+/// `wasm-pack build`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Build;
+
+/// `wasm-pack test`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Test;
+
+/// `wasm-pack pack`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pack;
+
+/// `wasm-pack --version`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Version;
+
+/// `wasm-pack login`, which logs in to the npm registry. Its output is just
+/// what it printed, so running returns a plain [`WasmPackOutput`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Login;
+
+impl Subcommand for Build {
+    type Output = BuildOutput;
+}
+
+impl Subcommand for Test {
+    type Output = TestOutput;
+}
+
+impl Subcommand for Pack {
+    type Output = PackOutput;
+}
+
+impl Subcommand for Version {
+    type Output = VersionOutput;
+}
+
+impl Subcommand for Login {
+    type Output = WasmPackOutput;
+}
+
+impl CrateSubcommand for Build {}
+impl CrateSubcommand for Test {}
+impl CrateSubcommand for Pack {}
+
+impl CompilingSubcommand for Build {}
+impl CompilingSubcommand for Test {}
+
+pub(crate) mod private {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Kind {
+        Build,
+        Test,
+        Pack,
+        Version,
+        Login,
+    }
+
+    impl Kind {
+        /// The subcommand's name, or `None` for `--version`, which is a flag.
+        pub fn name(&self) -> Option<&'static str> {
+            match self {
+                Kind::Build => Some("build"),
+                Kind::Test => Some("test"),
+                Kind::Pack => Some("pack"),
+                Kind::Version => None,
+                Kind::Login => Some("login"),
+            }
+        }
+
+        /// Whether the subcommand takes an input crate path.
+        pub fn takes_crate(&self) -> bool {
+            match self {
+                Kind::Build | Kind::Test | Kind::Pack => true,
+                Kind::Version | Kind::Login => false,
+            }
+        }
+    }
+
+    pub trait Sealed {
+        const KIND: Kind;
+
+        fn wrap(output: WasmPackOutput) -> <Self as Subcommand>::Output
+        where
+            Self: Subcommand;
+    }
+
+    impl Sealed for Build {
+        const KIND: Kind = Kind::Build;
+
+        fn wrap(output: WasmPackOutput) -> BuildOutput {
+            BuildOutput::new(output)
+        }
+    }
+
+    impl Sealed for Test {
+        const KIND: Kind = Kind::Test;
+
+        fn wrap(output: WasmPackOutput) -> TestOutput {
+            TestOutput::new(output)
+        }
+    }
+
+    impl Sealed for Pack {
+        const KIND: Kind = Kind::Pack;
+
+        fn wrap(output: WasmPackOutput) -> PackOutput {
+            PackOutput::new(output)
+        }
+    }
+
+    impl Sealed for Version {
+        const KIND: Kind = Kind::Version;
+
+        fn wrap(output: WasmPackOutput) -> VersionOutput {
+            VersionOutput::new(output)
+        }
+    }
+
+    impl Sealed for Login {
+        const KIND: Kind = Kind::Login;
+
+        fn wrap(output: WasmPackOutput) -> WasmPackOutput {
+            output
+        }
+    }
+}
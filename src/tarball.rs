@@ -0,0 +1,248 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{BuildProfile, Target, WasmPack, WasmPackError};
+
+/// Build `input_crate` with `profile` for `target`, and return a tarball of
+/// the out-dir, without leaving anything on disk.
+///
+/// The build goes into a temporary out-dir under `target`, which is deleted
+/// afterwards, even if the build fails. Paths in the tarball are relative to
+/// the out-dir, like `my_crate_bg.wasm` and `snippets/...`.
+///
+/// The tarball is the same for the same files: entries are sorted by path,
+/// and their owners and modification times are left out. Files are stored
+/// with mode `0644` and directories with `0755`.
+pub fn build_to_tar(
+    input_crate: impl Into<PathBuf>,
+    profile: BuildProfile,
+    target: Target,
+) -> Result<Vec<u8>, WasmPackError> {
+    let build = WasmPack::build(input_crate).profile(profile).target(target);
+    tar_build(build)
+}
+
+/// Like [`build_to_tar`], but gzips the tarball at `level`, from 0 (no
+/// compression) to 9 (best compression). Higher levels are a
+/// [`WasmPackError::InvalidConfiguration`] error, before anything is built.
+pub fn build_to_tar_gz(
+    input_crate: impl Into<PathBuf>,
+    profile: BuildProfile,
+    target: Target,
+    level: u32,
+) -> Result<Vec<u8>, WasmPackError> {
+    crate::gzip::check_level(level)
+        .map_err(|error| WasmPackError::InvalidConfiguration(error.to_string()))?;
+    let tarball = build_to_tar(input_crate, profile, target)?;
+    crate::gzip::compress(&tarball, level).map_err(WasmPackError::CouldntTarOutput)
+}
+
+fn tar_build(build: WasmPack) -> Result<Vec<u8>, WasmPackError> {
+    let out_dir = Path::new("target").join(format!("wasm-pack-tar-{}", uuid::Uuid::new_v4()));
+    let output = build
+        .out_dir_from_current_dir(&out_dir)
+        .ephemeral(true)
+        .run()?;
+
+    // The out-dir is deleted when `output` is dropped.
+    tar_dir(output.out_dir()).map_err(WasmPackError::CouldntTarOutput)
+}
+
+const BLOCK_SIZE: usize = 512;
+
+/// A ustar archive of everything in `dir`, sorted by path.
+pub(crate) fn tar_dir(dir: &Path) -> io::Result<Vec<u8>> {
+    let mut entries = Vec::new();
+    collect_entries(dir, Path::new(""), &mut entries)?;
+    entries.sort();
+
+    let mut tarball = Vec::new();
+    for relative_path in entries {
+        let path = dir.join(&relative_path);
+        let name = relative_path
+            .iter()
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if path.is_dir() {
+            tarball.extend_from_slice(&header(&format!("{}/", name), 0o755, 0, b'5')?);
+        } else {
+            let contents = std::fs::read(&path)?;
+            tarball.extend_from_slice(&header(&name, 0o644, contents.len() as u64, b'0')?);
+            tarball.extend_from_slice(&contents);
+            let padding = (BLOCK_SIZE - contents.len() % BLOCK_SIZE) % BLOCK_SIZE;
+            tarball.resize(tarball.len() + padding, 0);
+        }
+    }
+
+    // An archive ends with two empty blocks.
+    tarball.resize(tarball.len() + 2 * BLOCK_SIZE, 0);
+    Ok(tarball)
+}
+
+fn collect_entries(root: &Path, relative_dir: &Path, entries: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(root.join(relative_dir))? {
+        let entry = entry?;
+        let relative_path = relative_dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_entries(root, &relative_path, entries)?;
+        }
+        entries.push(relative_path);
+    }
+    Ok(())
+}
+
+/// A ustar header for an entry named `name`.
+fn header(name: &str, mode: u32, size: u64, kind: u8) -> io::Result<[u8; BLOCK_SIZE]> {
+    let mut header = [0; BLOCK_SIZE];
+    let (prefix, name) = split_name(name)?;
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], mode.into());
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], 0);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is worked out as if its own field were spaces.
+    header[148..156].fill(b' ');
+    let checksum = header.iter().map(|&byte| u64::from(byte)).sum::<u64>();
+    write_octal(&mut header[148..155], checksum);
+    Ok(header)
+}
+
+/// Split `name` into ustar's 155-byte prefix and 100-byte name fields, at a
+/// `/`, if it's too long for the name field alone.
+fn split_name(name: &str) -> io::Result<(&str, &str)> {
+    if name.len() <= 100 {
+        return Ok(("", name));
+    }
+
+    name.match_indices('/')
+        .map(|(index, _)| (&name[..index], &name[index + 1..]))
+        .find(|(prefix, rest)| prefix.len() <= 155 && rest.len() <= 100 && !rest.is_empty())
+        .ok_or_else(|| {
+            let message = format!("{:?} is too long for a tarball", name);
+            io::Error::new(io::ErrorKind::InvalidInput, message)
+        })
+}
+
+/// Write `value` as zero-padded octal, ending with a NUL byte.
+fn write_octal(field: &mut [u8], value: u64) {
+    let last = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = last);
+    field[..last].copy_from_slice(digits.as_bytes());
+    field[last] = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The name, type and contents of each entry in `tarball`.
+    fn read_entries(tarball: &[u8]) -> Vec<(String, u8, Vec<u8>)> {
+        let field = |bytes: &[u8]| {
+            let end = bytes
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(bytes.len());
+            String::from_utf8(bytes[..end].to_vec()).unwrap()
+        };
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while tarball[offset..offset + BLOCK_SIZE]
+            .iter()
+            .any(|&byte| byte != 0)
+        {
+            let header = &tarball[offset..offset + BLOCK_SIZE];
+            let mut unsummed = header.to_vec();
+            unsummed[148..156].fill(b' ');
+            let checksum = unsummed.iter().map(|&byte| u64::from(byte)).sum::<u64>();
+            assert_eq!(
+                u64::from_str_radix(&field(&header[148..156]), 8),
+                Ok(checksum)
+            );
+
+            let prefix = field(&header[345..500]);
+            let name = match prefix.is_empty() {
+                true => field(&header[..100]),
+                false => format!("{}/{}", prefix, field(&header[..100])),
+            };
+            let size = usize::from_str_radix(&field(&header[124..136]), 8).unwrap();
+            offset += BLOCK_SIZE;
+            entries.push((name, header[156], tarball[offset..offset + size].to_vec()));
+            offset += size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+        }
+
+        assert_eq!(tarball.len(), offset + 2 * BLOCK_SIZE);
+        entries
+    }
+
+    #[test]
+    fn tarring_a_directory() {
+        let dir = Path::new("target/tarball-test");
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+        let long_dir = "a".repeat(80);
+        std::fs::create_dir_all(dir.join("snippets").join(&long_dir)).unwrap();
+        std::fs::write(dir.join("test_crate.js"), "export {};\n").unwrap();
+        std::fs::write(dir.join("test_crate_bg.wasm"), vec![7; 600]).unwrap();
+        let long_file = format!("snippets/{}/{}.js", long_dir, "b".repeat(30));
+        std::fs::write(dir.join(&long_file), "").unwrap();
+
+        let tarball = tar_dir(dir).unwrap();
+        assert_eq!(tarball, tar_dir(dir).unwrap());
+        assert_eq!(
+            read_entries(&tarball),
+            vec![
+                ("snippets/".to_string(), b'5', Vec::new()),
+                (format!("snippets/{}/", long_dir), b'5', Vec::new()),
+                (long_file, b'0', Vec::new()),
+                ("test_crate.js".to_string(), b'0', b"export {};\n".to_vec()),
+                ("test_crate_bg.wasm".to_string(), b'0', vec![7; 600]),
+            ]
+        );
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn tarring_a_build() {
+        use crate::test_support::fake_wasm_pack;
+
+        let dir = Path::new("target/tar-build-test");
+        let contents = "#!/bin/sh\nwhile [ \"$1\" != --out-dir ]; do shift; done\nmkdir -p \"test-crate/$2\"\necho hi > \"test-crate/$2/test_crate.js\"\n";
+        let script = fake_wasm_pack(dir, contents);
+
+        let tarball = tar_build(WasmPack::build("test-crate").executable(&script)).unwrap();
+        assert_eq!(
+            read_entries(&tarball),
+            vec![("test_crate.js".to_string(), b'0', b"hi\n".to_vec())]
+        );
+
+        let leftovers = std::fs::read_dir("target")
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("wasm-pack-tar-")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let _ignore_errors = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn gzipped_tarballs_need_a_valid_level() {
+        let result = build_to_tar_gz("test-crate", BuildProfile::Dev, Target::Web, 10);
+        assert!(matches!(
+            result,
+            Err(WasmPackError::InvalidConfiguration(reason)) if reason.contains("0 to 9")
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+/// The JS environment wasm-pack generates bindings for, set with `--target`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// An ES module for bundlers like webpack. This is wasm-pack's default.
+    Bundler,
+    /// A CommonJS module for Node.js.
+    NodeJs,
+    /// An ES module that can be loaded directly in a browser.
+    Web,
+    /// A plain script for a `<script>` tag, which defines a global called
+    /// `wasm_bindgen` instead of exporting anything.
+    NoModules,
+}
+
+/// The global that wasm-pack 0.12.1 always defines for [`Target::NoModules`].
+pub(crate) const NO_MODULES_GLOBAL: &str = "wasm_bindgen";
+
+impl Target {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Target::Bundler => "bundler",
+            Target::NodeJs => "nodejs",
+            Target::Web => "web",
+            Target::NoModules => "no-modules",
+        }
+    }
+
+    /// Check that the generated entry JS looks like what wasm-pack generates
+    /// for this target, returning what's wrong if it doesn't.
+    pub(crate) fn check_entry_js(&self, js: &str) -> Result<(), String> {
+        let required: &[&str] = match self {
+            Target::Bundler => &["import * as wasm from", "export"],
+            Target::NodeJs => &["require(", "module.exports"],
+            Target::Web => &["import.meta.url", "__wbg_init"],
+            // The script assigns an immediately invoked function's result to
+            // the global.
+            Target::NoModules => &["let wasm_bindgen", "(function(", "__wbg_init"],
+        };
+
+        match required.iter().find(|snippet| !js.contains(*snippet)) {
+            Some(missing) => Err(format!(
+                "the generated JS doesn't contain `{}`, so it isn't a {} module",
+                missing,
+                self.as_str()
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The statement in the entry JS that wasm-pack generates for `target` that
+/// loads the `.wasm` file, like `import * as wasm from "./my_crate_bg.wasm";`
+/// for [`Target::Bundler`]. Assert that the entry JS contains it to check a
+/// build was for the right target.
+///
+/// Only this statement is predictable: the rest of the file depends on what
+/// the crate exports and on the crate's wasm-bindgen version. The file names
+/// come from `out_name` if it's set with `--out-name`, and from `crate_name`
+/// otherwise.
+///
+/// [`Target::NoModules`] scripts find the `.wasm` file from their own URL, so
+/// they don't name it. For them, this is the declaration of the global.
+pub fn expected_entry_js(target: Target, crate_name: &str, out_name: Option<&str>) -> String {
+    let name = match out_name {
+        Some(out_name) => out_name.to_string(),
+        None => crate_name.replace('-', "_"),
+    };
+
+    match target {
+        Target::Bundler => format!(r#"import * as wasm from "./{}_bg.wasm";"#, name),
+        Target::NodeJs => format!("`${{__dirname}}/{}_bg.wasm`", name),
+        Target::Web => format!("new URL('{}_bg.wasm', import.meta.url)", name),
+        Target::NoModules => format!("let {}", NO_MODULES_GLOBAL),
+    }
+}
+
+/// Find the JS file that consumers import, as opposed to the `*_bg.js` glue.
+pub(crate) fn find_entry_js(generated_files: &[PathBuf]) -> Option<&Path> {
+    generated_files
+        .iter()
+        .map(PathBuf::as_path)
+        .filter(|path| path.extension().is_some_and(|extension| extension == "js"))
+        .find(|path| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            !stem.ends_with("_bg")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLER_JS: &str = r#"import * as wasm from "./test_crate_bg.wasm";
+import { __wbg_set_wasm } from "./test_crate_bg.js";
+__wbg_set_wasm(wasm);
+export * from "./test_crate_bg.js";
+"#;
+
+    #[test]
+    fn checking_entry_js() {
+        assert_eq!(Target::Bundler.check_entry_js(BUNDLER_JS), Ok(()));
+        assert!(Target::Web.check_entry_js(BUNDLER_JS).is_err());
+        assert!(Target::NodeJs.check_entry_js(BUNDLER_JS).is_err());
+    }
+
+    #[test]
+    fn checking_no_modules_entry_js() {
+        let js = "let wasm_bindgen = (function(exports) {\n    async function __wbg_init() {}\n    return Object.assign(__wbg_init, exports);\n})({ __proto__: null });\n";
+        assert_eq!(Target::NoModules.check_entry_js(js), Ok(()));
+        assert!(Target::NoModules.check_entry_js(BUNDLER_JS).is_err());
+        assert!(js.contains(&expected_entry_js(Target::NoModules, "test-crate", None)));
+    }
+
+    #[test]
+    fn expected_entry_js_names_the_wasm_file() {
+        assert_eq!(
+            expected_entry_js(Target::Bundler, "test-crate", None),
+            r#"import * as wasm from "./test_crate_bg.wasm";"#
+        );
+        assert!(BUNDLER_JS.contains(&expected_entry_js(Target::Bundler, "test-crate", None)));
+        assert_eq!(
+            expected_entry_js(Target::NodeJs, "test-crate", Some("app")),
+            "`${__dirname}/app_bg.wasm`"
+        );
+        assert_eq!(
+            expected_entry_js(Target::Web, "test-crate", None),
+            "new URL('test_crate_bg.wasm', import.meta.url)"
+        );
+    }
+
+    #[test]
+    fn finding_entry_js() {
+        let files = vec![
+            PathBuf::from("pkg/package.json"),
+            PathBuf::from("pkg/test_crate.d.ts"),
+            PathBuf::from("pkg/test_crate.js"),
+            PathBuf::from("pkg/test_crate_bg.js"),
+            PathBuf::from("pkg/test_crate_bg.wasm"),
+        ];
+        assert_eq!(find_entry_js(&files), Some(Path::new("pkg/test_crate.js")));
+        assert_eq!(find_entry_js(&files[3..]), None);
+    }
+}
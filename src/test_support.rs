@@ -0,0 +1,18 @@
+//! Fixtures shared by the unit tests and the integration tests, which include
+//! this file with `#[path]`.
+
+use std::path::{Path, PathBuf};
+
+/// Write `script` to `dir/wasm-pack` and make it executable, to stand in for
+/// wasm-pack with [`WasmPack::executable`](crate::WasmPack::executable).
+/// `dir` is created if it doesn't exist.
+#[cfg(unix)]
+pub(crate) fn fake_wasm_pack(dir: &Path, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::create_dir_all(dir).unwrap();
+    let path = dir.join("wasm-pack");
+    std::fs::write(&path, script).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
@@ -0,0 +1,94 @@
+//! Checking for newer wasm-pack releases, behind the `update-check` feature.
+
+use std::io;
+
+use crate::run_id::run_log;
+use crate::{parse_version_output, Version, WasmPackError, WASM_PACK_VERSION};
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/rustwasm/wasm-pack/releases/latest";
+
+/// Ask GitHub for the latest wasm-pack release, and log a warning if it's
+/// newer than the embedded wasm-pack. Returns the latest release's version.
+///
+/// This needs network access to `api.github.com`, and is only done when it's
+/// called, never as part of running wasm-pack.
+pub fn check_for_updates() -> Result<Version, WasmPackError> {
+    let latest = latest_release().map_err(WasmPackError::CouldntCheckForUpdates)?;
+
+    if is_newer(&latest, &embedded_version()) {
+        run_log!(
+            warn,
+            "wasm-pack {} is out, but lib_wasm_pack embeds wasm-pack {}.",
+            latest,
+            WASM_PACK_VERSION
+        );
+    } else {
+        run_log!(debug, "The embedded wasm-pack is up to date.");
+    }
+
+    Ok(latest)
+}
+
+fn latest_release() -> io::Result<Version> {
+    run_log!(
+        debug,
+        "Checking for wasm-pack releases at {}",
+        LATEST_RELEASE_URL
+    );
+    let body = ureq::get(LATEST_RELEASE_URL)
+        .header("Accept", "application/vnd.github+json")
+        .call()
+        .map_err(io::Error::other)?
+        .into_body()
+        .read_to_string()
+        .map_err(io::Error::other)?;
+
+    let release: serde_json::Value = serde_json::from_str(&body)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    let tag = release["tag_name"].as_str().unwrap_or_default();
+    parse_tag(tag).ok_or_else(|| {
+        let message = format!("couldn't parse the release tag {:?}", tag);
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    })
+}
+
+/// Parse a release tag, like `v0.13.1`.
+fn parse_tag(tag: &str) -> Option<Version> {
+    let version = tag.strip_prefix('v').unwrap_or(tag);
+    parse_version_output(&format!("wasm-pack {}", version))
+}
+
+fn embedded_version() -> Version {
+    parse_tag(WASM_PACK_VERSION).expect("The embedded version is always valid.")
+}
+
+/// Whether `version` comes after `current`. A pre-release comes before the
+/// release with the same number.
+fn is_newer(version: &Version, current: &Version) -> bool {
+    let numbers = |version: &Version| (version.major, version.minor, version.patch);
+    match numbers(version).cmp(&numbers(current)) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal => version.pre_release.is_none() && current.pre_release.is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparing_releases() {
+        let version = |tag| parse_tag(tag).unwrap();
+        let current = embedded_version();
+        assert_eq!(current.to_string(), WASM_PACK_VERSION);
+
+        assert!(is_newer(&version("v0.13.1"), &current));
+        assert!(is_newer(&version("v1.0.0"), &current));
+        assert!(!is_newer(&version("v0.12.1"), &current));
+        assert!(!is_newer(&version("v0.12.0"), &current));
+        assert!(!is_newer(&version("v0.12.2-rc.1"), &version("v0.12.2")));
+        assert!(is_newer(&version("v0.12.2"), &version("v0.12.2-rc.1")));
+        assert_eq!(parse_tag("latest"), None);
+    }
+}
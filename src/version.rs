@@ -0,0 +1,92 @@
+/// A wasm-pack version, as printed by `wasm-pack --version`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Like `"rc.1"` in `0.13.0-rc.1`.
+    pub pre_release: Option<String>,
+    /// Anything printed after the version, like the git hash and date that
+    /// builds from source add, without surrounding parentheses.
+    pub build_info: Option<String>,
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre_release) = &self.pre_release {
+            write!(f, "-{}", pre_release)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the output of `wasm-pack --version`, which looks like
+/// `wasm-pack 0.12.1`, optionally followed by build info like
+/// `(1a2b3c4 2023-06-01)`. Works for custom executables too.
+///
+/// ```
+/// let version = lib_wasm_pack::parse_version_output("wasm-pack 0.12.1\n").unwrap();
+/// assert_eq!(version.to_string(), "0.12.1");
+/// ```
+pub fn parse_version_output(output: &str) -> Option<Version> {
+    let rest = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("wasm-pack "))?
+        .trim();
+    let (version, build_info) = match rest.split_once(char::is_whitespace) {
+        Some((version, build_info)) => (version, Some(build_info)),
+        None => (rest, None),
+    };
+
+    let (version, pre_release) = match version.split_once('-') {
+        Some((version, pre_release)) => (version, Some(pre_release.to_string())),
+        None => (version, None),
+    };
+    let mut numbers = version.split('.').map(|number| number.parse::<u64>().ok());
+    let (major, minor, patch) = (numbers.next()??, numbers.next()??, numbers.next()??);
+    if numbers.next().is_some() {
+        return None;
+    }
+
+    let build_info = build_info
+        .map(|build_info| {
+            let build_info = build_info.trim();
+            let build_info = build_info.strip_prefix('(').unwrap_or(build_info);
+            build_info
+                .strip_suffix(')')
+                .unwrap_or(build_info)
+                .to_string()
+        })
+        .filter(|build_info| !build_info.is_empty());
+
+    Some(Version {
+        major,
+        minor,
+        patch,
+        pre_release,
+        build_info,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_version_output() {
+        let output = crate::run(["--version"]).expect("Couldn't run `wasm-pack --version`.");
+        let version = parse_version_output(output.stdout()).unwrap();
+        assert_eq!(version.to_string(), crate::WASM_PACK_VERSION);
+        assert_eq!(version.build_info, None);
+
+        let version = parse_version_output("wasm-pack 0.13.0-rc.1 (1a2b3c4 2024-06-01)").unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (0, 13, 0));
+        assert_eq!(version.pre_release.as_deref(), Some("rc.1"));
+        assert_eq!(version.build_info.as_deref(), Some("1a2b3c4 2024-06-01"));
+
+        assert_eq!(parse_version_output("wasm-pack"), None);
+        assert_eq!(parse_version_output("cargo 1.75.0"), None);
+        assert_eq!(parse_version_output("wasm-pack 0.12"), None);
+    }
+}
@@ -0,0 +1,67 @@
+/// How hard wasm-opt should optimize, matching its `-O` flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptLevel {
+    /// `-O`: wasm-opt's default optimizations.
+    Default,
+    /// `-O1` through `-O4`. Higher levels are slower but produce faster code.
+    Speed(u8),
+    /// `-Os`: optimize for size.
+    Size,
+    /// `-Oz`: optimize aggressively for size.
+    MinSize,
+}
+
+impl OptLevel {
+    pub(crate) fn as_arg(&self) -> String {
+        match self {
+            OptLevel::Default => "-O".to_string(),
+            OptLevel::Speed(level) => format!("-O{}", level),
+            OptLevel::Size => "-Os".to_string(),
+            OptLevel::MinSize => "-Oz".to_string(),
+        }
+    }
+}
+
+/// What wasm-pack logs when it starts running wasm-opt.
+const RUNNING_WASM_OPT: &str = "Optimizing wasm binaries with `wasm-opt`";
+
+/// Whether wasm-pack's stderr says it ran wasm-opt.
+pub(crate) fn ran(stderr: &str) -> bool {
+    stderr.contains(RUNNING_WASM_OPT)
+}
+
+/// Explain how to turn wasm-opt off, since wasm-pack 0.12.1 has no flag for
+/// it.
+pub(crate) fn no_opt_unsupported_reason() -> String {
+    "wasm-pack only reads whether to run wasm-opt from the crate's Cargo.toml. Add `wasm-opt = false` to `[package.metadata.wasm-pack.profile.release]` instead, or build with `BuildProfile::Dev`, which never runs it".to_string()
+}
+
+/// Explain how to configure wasm-opt, since wasm-pack only reads its
+/// arguments from the crate's manifest.
+pub(crate) fn unsupported_reason(args: &[String]) -> String {
+    format!(
+        "wasm-pack only reads wasm-opt arguments from the crate's Cargo.toml. Add `wasm-opt = {:?}` to `[package.metadata.wasm-pack.profile.release]` instead",
+        args
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opt_level_args() {
+        assert_eq!(OptLevel::Default.as_arg(), "-O");
+        assert_eq!(OptLevel::Speed(3).as_arg(), "-O3");
+        assert_eq!(OptLevel::Size.as_arg(), "-Os");
+        assert_eq!(OptLevel::MinSize.as_arg(), "-Oz");
+    }
+
+    #[test]
+    fn telling_whether_wasm_opt_ran() {
+        assert!(ran(
+            "[INFO]: Installing wasm-bindgen...\n[INFO]: Optimizing wasm binaries with `wasm-opt`...\n"
+        ));
+        assert!(!ran("[INFO]: Installing wasm-bindgen...\n"));
+    }
+}
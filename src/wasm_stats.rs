@@ -0,0 +1,114 @@
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+
+/// How many imports, exports and functions the `.wasm` a build generated
+/// has, from [`BuildOutput::wasm_stats`](crate::BuildOutput::wasm_stats).
+///
+/// With more than one `*_bg.wasm` file, the counts are added up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WasmStats {
+    /// Everything the module imports, including functions, memories, tables
+    /// and globals.
+    pub imports: usize,
+    /// Everything the module exports.
+    pub exports: usize,
+    /// The functions the module defines itself, not counting imported ones.
+    pub functions: usize,
+}
+
+impl Display for WasmStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "imports: {}, exports: {}, functions: {}",
+            self.imports, self.exports, self.functions
+        )
+    }
+}
+
+/// The stats of the `*_bg.wasm` files in `generated_files`, added up.
+pub(crate) fn for_files(generated_files: &[impl AsRef<Path>]) -> io::Result<WasmStats> {
+    let mut total = WasmStats::default();
+    for path in generated_files {
+        let path = path.as_ref();
+        if crate::gzip::is_bg_wasm(path) {
+            let stats = count(&std::fs::read(path)?).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+            })?;
+            total.imports += stats.imports;
+            total.exports += stats.exports;
+            total.functions += stats.functions;
+        }
+    }
+    Ok(total)
+}
+
+fn count(wasm: &[u8]) -> io::Result<WasmStats> {
+    let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+
+    let mut stats = WasmStats::default();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        match payload.map_err(invalid)? {
+            // Imports can be grouped, so the section's count isn't always the
+            // number of imports.
+            wasmparser::Payload::ImportSection(reader) => {
+                for import in reader.into_imports() {
+                    import.map_err(invalid)?;
+                    stats.imports += 1;
+                }
+            }
+            wasmparser::Payload::ExportSection(reader) => stats.exports += reader.count() as usize,
+            wasmparser::Payload::FunctionSection(reader) => {
+                stats.functions += reader.count() as usize
+            }
+            _ => {}
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A section with the given ID and contents.
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut section = vec![id, contents.len() as u8];
+        section.extend_from_slice(contents);
+        section
+    }
+
+    #[test]
+    fn counting_imports_exports_and_functions() {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        // One type: a function with no parameters or results.
+        wasm.extend(section(1, &[1, 0x60, 0, 0]));
+        // Two imported functions, `env.a` and `env.b`.
+        wasm.extend(section(
+            2,
+            &[
+                2, 3, b'e', b'n', b'v', 1, b'a', 0, 0, 3, b'e', b'n', b'v', 1, b'b', 0, 0,
+            ],
+        ));
+        // Three functions of that type.
+        wasm.extend(section(3, &[3, 0, 0, 0]));
+        // One export, `f`, of the first defined function.
+        wasm.extend(section(7, &[1, 1, b'f', 0, 2]));
+        // Their bodies, which do nothing.
+        wasm.extend(section(10, &[3, 2, 0, 0x0b, 2, 0, 0x0b, 2, 0, 0x0b]));
+
+        let stats = count(&wasm).unwrap();
+        assert_eq!(
+            stats,
+            WasmStats {
+                imports: 2,
+                exports: 1,
+                functions: 3,
+            }
+        );
+        assert_eq!(stats.to_string(), "imports: 2, exports: 1, functions: 3");
+        assert_eq!(count(b"\0asm\x01\0\0\0").unwrap(), WasmStats::default());
+        assert!(count(b"not wasm").is_err());
+    }
+}
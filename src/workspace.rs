@@ -0,0 +1,94 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::WasmPackError;
+
+/// Find the directory of the workspace member called `name`, using
+/// `cargo metadata` run from `workspace_dir`.
+pub(crate) fn find_member_dir(workspace_dir: &Path, name: &str) -> Result<PathBuf, WasmPackError> {
+    let metadata =
+        read_cargo_metadata(workspace_dir).map_err(WasmPackError::CouldntReadCargoMetadata)?;
+
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    let package = packages
+        .iter()
+        .find(|package| package["name"] == name)
+        .ok_or_else(|| WasmPackError::WorkspaceMemberNotFound {
+            name: name.to_string(),
+        })?;
+
+    // wasm-pack needs a cdylib to produce a .wasm file.
+    let is_cdylib = package["targets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|target| target["crate_types"].as_array().into_iter().flatten())
+        .any(|crate_type| crate_type == "cdylib");
+
+    if !is_cdylib {
+        return Err(WasmPackError::WorkspaceMemberIsntACdylib {
+            name: name.to_string(),
+        });
+    }
+
+    let manifest_path = package["manifest_path"].as_str().ok_or_else(|| {
+        let error = io::Error::new(io::ErrorKind::InvalidData, "package has no manifest_path");
+        WasmPackError::CouldntReadCargoMetadata(error)
+    })?;
+
+    let member_dir = Path::new(manifest_path)
+        .parent()
+        .expect("Manifest paths always have a parent directory.")
+        .to_path_buf();
+
+    Ok(member_dir)
+}
+
+pub(crate) fn read_cargo_metadata(workspace_dir: &Path) -> io::Result<serde_json::Value> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = duct::cmd!(cargo, "metadata", "--format-version", "1", "--no-deps")
+        .dir(workspace_dir)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(io::Error::other(stderr));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finding_a_workspace_member() {
+        let member_dir = find_member_dir(Path::new("test-crate"), "test-crate")
+            .expect("Couldn't find test-crate.");
+        assert!(member_dir.ends_with("test-crate"));
+        assert!(member_dir.join("Cargo.toml").exists());
+    }
+
+    #[test]
+    fn missing_workspace_member() {
+        let result = find_member_dir(Path::new("test-crate"), "fake-crate");
+        assert!(matches!(
+            result,
+            Err(WasmPackError::WorkspaceMemberNotFound { name }) if name == "fake-crate"
+        ));
+    }
+
+    #[test]
+    fn workspace_member_without_cdylib() {
+        let result = find_member_dir(Path::new("."), "lib_wasm_pack");
+        assert!(matches!(
+            result,
+            Err(WasmPackError::WorkspaceMemberIsntACdylib { name }) if name == "lib_wasm_pack"
+        ));
+    }
+}
@@ -0,0 +1,49 @@
+//! This sends a real SIGINT to the test process, so it lives in its own test
+//! binary where no other runs can see it.
+#![cfg(unix)]
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use lib_wasm_pack::{WasmPack, WasmPackError};
+
+#[path = "../src/test_support.rs"]
+mod test_support;
+use test_support::fake_wasm_pack;
+
+#[test]
+fn interrupting_stops_wasm_pack() {
+    let dir = Path::new("target/interrupt-test");
+    std::fs::create_dir_all(dir).unwrap();
+    let started_marker = dir.join("started");
+    let _ignore_errors = std::fs::remove_file(&started_marker);
+
+    // Stands in for a long build. `exec` makes `sleep` the process we kill.
+    let contents = format!(
+        "#!/bin/sh\ntouch '{}'\nexec sleep 30\n",
+        started_marker.display()
+    );
+    let script = fake_wasm_pack(dir, &contents);
+
+    let start = Instant::now();
+    let build = std::thread::spawn({
+        let script = script.clone();
+        move || WasmPack::build("test-crate").executable(script).run()
+    });
+
+    // The handler is installed before wasm-pack starts.
+    while !started_marker.exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "wasm-pack never started."
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    unsafe { libc::raise(libc::SIGINT) };
+
+    let result = build.join().unwrap();
+    assert!(matches!(result, Err(WasmPackError::Interrupted)));
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    let _ignore_errors = std::fs::remove_dir_all(dir);
+}